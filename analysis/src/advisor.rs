@@ -0,0 +1,179 @@
+use sandy_layout::math::{estimate_max_front_run, largest_filled};
+use serde::Serialize;
+
+use crate::{
+    pool::Pool,
+    simulator::{simulate_sandwich, SandwichEstimate},
+};
+
+/// Largest front-run after which a swap of `amount_in` through `pool` still
+/// gets `minimum_amount_out`, or `None` if it falls short even without one.
+pub fn max_front_run(pool: Pool, amount_in: u64, minimum_amount_out: u64) -> Option<u64> {
+    let guess = estimate_max_front_run(
+        amount_in,
+        minimum_amount_out,
+        pool.reserve_in,
+        pool.reserve_out,
+        pool.fee_numerator,
+        pool.fee_denominator,
+    );
+
+    largest_filled(0, u64::MAX, guess, |front_amount_in| {
+        let mut pool = pool;
+        pool.swap(front_amount_in);
+        pool.amount_out(amount_in) >= minimum_amount_out
+    })
+}
+
+// the attacker's profit rises with the front-run until its own price impact
+// and fees outweigh the swap's, so a ternary search finds the top
+fn most_profitable_front_run(pool: Pool, amount_in: u64) -> u64 {
+    let profit =
+        |front_amount_in| simulate_sandwich(pool, front_amount_in, &[amount_in]).attacker_profit;
+
+    let (mut lb, mut ub) = (0u64, u64::MAX);
+    while ub - lb > 2 {
+        let third = (ub - lb) / 3;
+        let (left, right) = (lb + third, ub - third);
+        if profit(left) < profit(right) {
+            lb = left;
+        } else {
+            ub = right;
+        }
+    }
+
+    // rounding makes tiny front-runs lose a little more or less at random,
+    // which can lead the search away from not front-running at all
+    (lb..=ub)
+        .chain([0])
+        .max_by_key(|front_amount_in| profit(*front_amount_in))
+        .unwrap_or(lb)
+}
+
+/// What an attacker can take from a swap with a given minimum output.
+#[derive(Debug, Serialize)]
+pub struct Exposure {
+    /// The most profitable front-run the minimum still lets the swap through
+    /// after. An attacker only goes ahead if it makes a profit.
+    pub front_run: u64,
+    pub estimate: SandwichEstimate,
+}
+
+/// `None` if the swap would fail on its own.
+pub fn exposure(pool: Pool, amount_in: u64, minimum_amount_out: u64) -> Option<Exposure> {
+    let front_run = max_front_run(pool, amount_in, minimum_amount_out)?
+        .min(most_profitable_front_run(pool, amount_in));
+
+    Some(Exposure {
+        front_run,
+        estimate: simulate_sandwich(pool, front_run, &[amount_in]),
+    })
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Advice {
+    pub expected_out: u64,
+    /// Loosest minimum at which no front-run pays for itself, or the
+    /// expected output if even that one leaves room for a profitable one.
+    pub minimum_amount_out: u64,
+    pub slippage_bps: u64,
+}
+
+/// Advises the minimum output for a swap of `amount_in` through `pool`.
+pub fn advise(pool: Pool, amount_in: u64) -> Advice {
+    let expected_out = pool.amount_out(amount_in);
+    let best_front_run = most_profitable_front_run(pool, amount_in);
+
+    // the allowed front-run only shrinks as the minimum goes up, and below
+    // the most profitable one so does the profit
+    let safe = |minimum_amount_out| match max_front_run(pool, amount_in, minimum_amount_out) {
+        Some(front_run) => {
+            let front_run = front_run.min(best_front_run);
+            simulate_sandwich(pool, front_run, &[amount_in]).attacker_profit <= 0
+        }
+        None => true,
+    };
+
+    let minimum_amount_out = if safe(0) {
+        0
+    } else {
+        let (mut lb, mut ub) = (0, expected_out);
+        while ub - lb > 1 {
+            let mid = lb + (ub - lb) / 2;
+            if safe(mid) {
+                ub = mid;
+            } else {
+                lb = mid;
+            }
+        }
+        ub
+    };
+
+    let slippage_bps = match expected_out {
+        0 => 0,
+        _ => ((expected_out - minimum_amount_out) as u128 * 10_000 / expected_out as u128) as u64,
+    };
+
+    Advice {
+        expected_out,
+        minimum_amount_out,
+        slippage_bps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_front_run_finds_the_boundary() {
+        // a 250 SOL sell into 40k SOL against 6M USDC, at 1% slippage
+        let pool = Pool::raydium(40_000_000_000_000, 6_000_000_000_000);
+        let amount_in = 250_000_000_000;
+        let minimum_amount_out = pool.amount_out(amount_in) * 99 / 100;
+
+        let filled = |front_amount_in| {
+            let mut pool = pool;
+            pool.swap(front_amount_in);
+            pool.amount_out(amount_in) >= minimum_amount_out
+        };
+
+        let front_run = max_front_run(pool, amount_in, minimum_amount_out).unwrap();
+        assert!(filled(front_run));
+        assert!(!filled(front_run + 1));
+    }
+
+    #[test]
+    fn max_front_run_without_minimum_is_unbounded() {
+        assert_eq!(
+            max_front_run(Pool::raydium(1_000, 1_000), 10, 0),
+            Some(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn advised_minimum_leaves_nothing_to_take() {
+        // 40k SOL against 6M USDC, selling 250 SOL
+        let pool = Pool::raydium(40_000_000_000_000, 6_000_000_000_000);
+        let amount_in = 250_000_000_000;
+
+        let advice = advise(pool, amount_in);
+        assert!(advice.minimum_amount_out < advice.expected_out);
+
+        let at_advice = exposure(pool, amount_in, advice.minimum_amount_out).unwrap();
+        assert!(at_advice.estimate.attacker_profit <= 0);
+
+        let looser = advice.minimum_amount_out - advice.expected_out / 100;
+        let at_looser = exposure(pool, amount_in, looser).unwrap();
+        assert!(at_looser.estimate.attacker_profit > 0);
+        assert!(at_looser.front_run > at_advice.front_run);
+    }
+
+    #[test]
+    fn swap_that_would_fail_has_no_exposure() {
+        let pool = Pool::raydium(1_000_000, 1_000_000);
+        let expected_out = pool.amount_out(1_000);
+
+        assert!(exposure(pool, 1_000, expected_out + 1).is_none());
+    }
+}
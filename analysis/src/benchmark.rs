@@ -0,0 +1,314 @@
+use std::{collections::BTreeMap, fs, path::Path, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{hash::hash, pubkey::Pubkey, signature::Signature};
+
+use crate::{
+    decoder::SwapInstruction,
+    detector::{detect, ObservedSwap, Sandwich, Settlement},
+    pool::Pool,
+};
+
+/// A labelled sequence of swaps in execution order, with the sandwiches a
+/// reviewer found in it.
+pub struct Fixture {
+    /// File stem of the fixture.
+    pub name: String,
+    /// What the sequence is an example of, e.g. `sandwich` or `arbitrage`.
+    pub category: String,
+    pub source: Source,
+    pub swaps: Vec<ObservedSwap>,
+    pub sandwiches: Vec<Sandwich>,
+}
+
+/// Where a fixture's swaps came from, with a note saying how.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// Written by hand, so scores on it say nothing about real traffic.
+    Synthetic(String),
+    /// Taken from a recorded feed or a confirmed transaction dump.
+    Recorded(String),
+}
+
+// on disk, keys are either base58 addresses or names that stand in for one
+#[derive(Deserialize)]
+struct FixtureFile {
+    category: String,
+    source: Source,
+    swaps: Vec<SwapFile>,
+    sandwiches: Vec<Sandwich>,
+}
+
+#[derive(Deserialize)]
+struct SwapFile {
+    signature: Option<String>,
+    user: String,
+    pool: String,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    settlement: Option<SettlementFile>,
+}
+
+#[derive(Deserialize)]
+struct SettlementFile {
+    mint_in: String,
+    mint_out: String,
+    amount_in: u64,
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+}
+
+/// Loads every `.json` fixture in `dir`, sorted by name.
+pub fn load_corpus(dir: &Path) -> Result<Vec<Fixture>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| load(path).map_err(|e| anyhow!("{}: {e}", path.display())))
+        .collect()
+}
+
+fn load(path: &Path) -> Result<Fixture> {
+    let file: FixtureFile = serde_json::from_slice(&fs::read(path)?)?;
+
+    let swaps = file
+        .swaps
+        .into_iter()
+        .map(|swap| {
+            Ok(ObservedSwap {
+                signature: match swap.signature {
+                    Some(signature) => Signature::from_str(&signature)?,
+                    None => Signature::default(),
+                },
+                swap: SwapInstruction {
+                    amount_in: Some(swap.amount_in),
+                    minimum_amount_out: Some(swap.minimum_amount_out),
+                    amm_id: address(&swap.pool),
+                    user: address(&swap.user),
+                },
+                settlement: swap.settlement.map(|settlement| Settlement {
+                    mint_in: address(&settlement.mint_in),
+                    mint_out: address(&settlement.mint_out),
+                    amount_in: settlement.amount_in,
+                    amount_out: settlement.amount_out,
+                    pool_before: Pool::raydium(settlement.reserve_in, settlement.reserve_out),
+                }),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for sandwich in &file.sandwiches {
+        let mut indices = [sandwich.front, sandwich.back]
+            .into_iter()
+            .chain(sandwich.victims.iter().copied());
+        if indices.any(|index| index >= swaps.len()) {
+            bail!("sandwich {sandwich:?} is out of range");
+        }
+    }
+
+    Ok(Fixture {
+        name: path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        category: file.category,
+        source: file.source,
+        swaps,
+        sandwiches: file.sandwiches,
+    })
+}
+
+// the detector only compares keys, so a readable name works as well as the
+// real address
+fn address(key: &str) -> Pubkey {
+    Pubkey::from_str(key)
+        .unwrap_or_else(|_| Pubkey::new_from_array(hash(key.as_bytes()).to_bytes()))
+}
+
+/// Detections counted against the labels. A detection only counts as true
+/// when front-run, victims and back-run all match a labelled sandwich.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Score {
+    pub labelled: usize,
+    pub detected: usize,
+    pub true_positives: usize,
+}
+
+impl Score {
+    pub fn false_positives(&self) -> usize {
+        self.detected - self.true_positives
+    }
+
+    pub fn false_negatives(&self) -> usize {
+        self.labelled - self.true_positives
+    }
+
+    /// `None` when nothing was detected.
+    pub fn precision(&self) -> Option<f64> {
+        (self.detected > 0).then(|| self.true_positives as f64 / self.detected as f64)
+    }
+
+    /// `None` when nothing was labelled.
+    pub fn recall(&self) -> Option<f64> {
+        (self.labelled > 0).then(|| self.true_positives as f64 / self.labelled as f64)
+    }
+
+    fn add(&mut self, other: Score) {
+        self.labelled += other.labelled;
+        self.detected += other.detected;
+        self.true_positives += other.true_positives;
+    }
+}
+
+/// Runs the detector over one fixture.
+pub fn score(fixture: &Fixture) -> Score {
+    let detected = detect(&fixture.swaps);
+
+    Score {
+        labelled: fixture.sandwiches.len(),
+        detected: detected.len(),
+        true_positives: detected
+            .iter()
+            .filter(|sandwich| fixture.sandwiches.contains(sandwich))
+            .count(),
+    }
+}
+
+/// Scores over the whole corpus, overall and by category.
+pub struct Benchmark {
+    pub overall: Score,
+    /// Fixtures that were recorded rather than written by hand.
+    pub recorded: usize,
+    pub categories: BTreeMap<String, Score>,
+    /// Fixtures the detector got anything wrong on, with their scores.
+    pub misses: Vec<(String, Score)>,
+}
+
+pub fn benchmark(corpus: &[Fixture]) -> Benchmark {
+    let mut benchmark = Benchmark {
+        overall: Score::default(),
+        recorded: 0,
+        categories: BTreeMap::new(),
+        misses: Vec::new(),
+    };
+
+    for fixture in corpus {
+        let score = score(fixture);
+
+        benchmark.overall.add(score);
+        if matches!(fixture.source, Source::Recorded(_)) {
+            benchmark.recorded += 1;
+        }
+        benchmark
+            .categories
+            .entry(fixture.category.clone())
+            .or_default()
+            .add(score);
+
+        if score.false_positives() > 0 || score.false_negatives() > 0 {
+            benchmark.misses.push((fixture.name.clone(), score));
+        }
+    }
+
+    benchmark
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn corpus() -> Vec<Fixture> {
+        load_corpus(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/detector")).unwrap()
+    }
+
+    #[test]
+    fn corpus_covers_every_category() {
+        let corpus = corpus();
+
+        for category in ["sandwich", "organic", "arbitrage", "self_trade"] {
+            assert!(
+                corpus.iter().any(|fixture| fixture.category == category),
+                "no {category} fixtures"
+            );
+        }
+    }
+
+    #[test]
+    fn settlements_follow_the_pool_model() {
+        for fixture in corpus() {
+            // each pool as the last settled swap left it, seen from its input
+            let mut pools = HashMap::new();
+
+            for (i, swap) in fixture.swaps.iter().enumerate() {
+                let Some(settlement) = &swap.settlement else {
+                    continue;
+                };
+                let before = settlement.pool_before;
+                let context = format!("{} swap {i}", fixture.name);
+
+                if let Some((mint_in, after)) = pools.get(&swap.swap.amm_id) {
+                    let after: Pool = if *mint_in == settlement.mint_in {
+                        *after
+                    } else {
+                        after.reversed()
+                    };
+                    assert_eq!(
+                        (before.reserve_in, before.reserve_out),
+                        (after.reserve_in, after.reserve_out),
+                        "{context}"
+                    );
+                }
+
+                let mut after = before;
+                assert_eq!(
+                    after.swap(settlement.amount_in),
+                    settlement.amount_out,
+                    "{context}"
+                );
+                pools.insert(swap.swap.amm_id, (settlement.mint_in, after));
+            }
+        }
+    }
+
+    #[test]
+    fn names_stand_in_for_addresses() {
+        let key = Pubkey::new_unique();
+
+        assert_eq!(address(&key.to_string()), key);
+        assert_eq!(address("attacker"), address("attacker"));
+        assert_ne!(address("attacker"), address("victim"));
+    }
+
+    #[test]
+    fn counts_recorded_fixtures() {
+        let fixture = |source: &str| Fixture {
+            name: String::new(),
+            category: "organic".to_string(),
+            source: serde_json::from_str(source).unwrap(),
+            swaps: Vec::new(),
+            sandwiches: Vec::new(),
+        };
+
+        let mixed = [
+            fixture(r#"{"synthetic": "hand-written"}"#),
+            fixture(r#"{"recorded": "slot 288000000 of a recorded feed"}"#),
+        ];
+
+        assert_eq!(benchmark(&mixed).recorded, 1);
+    }
+}
@@ -2,24 +2,31 @@ use borsh::BorshDeserialize;
 use solana_sdk::pubkey;
 use solana_sdk::pubkey::Pubkey;
 
-use super::{raydium_amm::RAYDIUM_AMM_PROGRAM_ID, InstructionDecoder, SwapBaseIn, SwapInstruction};
+use super::{
+    account_key, raydium_amm::RAYDIUM_AMM_PROGRAM_ID, InstructionDecoder, SwapBaseIn,
+    SwapInstruction,
+};
 
 pub const BANANA_PROGRAM_ID: Pubkey = pubkey!("BANANAjs7FJiPQqJTGFzkZJndT9o7UmKiYYGaJz6frGu");
 
 pub struct BananaGun;
 
 impl InstructionDecoder for BananaGun {
+    fn name(&self) -> &'static str {
+        "banana_gun"
+    }
+
     fn decode_instruction(
         &self,
         data: &[u8],
         account_keys: &[Pubkey],
         accounts: &[u8],
     ) -> Option<SwapInstruction> {
-        if account_keys[accounts[3] as usize] != RAYDIUM_AMM_PROGRAM_ID {
+        if account_key(account_keys, accounts, 3)? != RAYDIUM_AMM_PROGRAM_ID {
             return None;
         }
 
-        let data = &data[data.len() - 16..];
+        let data = &data[data.len().checked_sub(16)?..];
 
         let instruction: SwapBaseIn = match SwapBaseIn::try_from_slice(data) {
             Ok(base_in) => base_in,
@@ -27,9 +34,11 @@ impl InstructionDecoder for BananaGun {
         };
 
         Some(SwapInstruction {
-            amount_in: instruction.amount_in,
-            minimum_amount_out: instruction.minimum_amount_out,
-            amm_id: account_keys[accounts[7] as usize],
+            amount_in: Some(instruction.amount_in),
+            minimum_amount_out: Some(instruction.minimum_amount_out),
+            amm_id: account_key(account_keys, accounts, 7)?,
+            // banana gun trades from the wallet paying for the transaction
+            user: *account_keys.first()?,
         })
     }
 }
@@ -0,0 +1,379 @@
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+use super::{
+    account_key, raydium_amm::RAYDIUM_AMM_PROGRAM_ID, InstructionDecoder, SwapInstruction,
+};
+
+pub const JUPITER_PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+const ROUTE_DISCRIMINATOR: [u8; 8] = [229, 23, 203, 151, 122, 227, 173, 42];
+const SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR: [u8; 8] = [193, 32, 155, 51, 65, 214, 156, 129];
+
+// in_amount, quoted_out_amount, slippage_bps and platform_fee_bps close out
+// the instruction data of every route variant
+const ROUTE_ARGS_LEN: usize = 8 + 8 + 2 + 1;
+
+const RAYDIUM_SWAP_TAG: u8 = 7;
+
+pub struct Jupiter;
+
+struct Route {
+    user_account: usize,
+    remaining_accounts: usize,
+    plan_offset: usize,
+}
+
+struct RoutePlanStep {
+    tag: u8,
+    percent: u8,
+    input_index: u8,
+}
+
+impl InstructionDecoder for Jupiter {
+    fn name(&self) -> &'static str {
+        "jupiter"
+    }
+
+    fn decode_instruction(
+        &self,
+        data: &[u8],
+        account_keys: &[Pubkey],
+        accounts: &[u8],
+    ) -> Option<SwapInstruction> {
+        self.decode_swaps(data, account_keys, accounts)
+            .into_iter()
+            .next()
+    }
+
+    /// Decodes the Raydium legs of a route, attributed to the user who signed
+    /// for it rather than the Jupiter program that executes them.
+    ///
+    /// Jupiter hands intermediate legs a minimum of zero and only checks the
+    /// route's final output, so legs of multi-step routes have no minimum of
+    /// their own. A leg's input is only known up front if it spends the
+    /// route's input, otherwise `amount_in` is `None`.
+    fn decode_swaps(
+        &self,
+        data: &[u8],
+        account_keys: &[Pubkey],
+        accounts: &[u8],
+    ) -> Vec<SwapInstruction> {
+        let route = match data.get(..8) {
+            Some(discriminator) if discriminator == ROUTE_DISCRIMINATOR => Route {
+                user_account: 1,
+                remaining_accounts: 9,
+                plan_offset: 8,
+            },
+            Some(discriminator) if discriminator == SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR => Route {
+                user_account: 2,
+                remaining_accounts: 13,
+                plan_offset: 9,
+            },
+            _ => return Vec::new(),
+        };
+
+        let Some(args) = data
+            .len()
+            .checked_sub(ROUTE_ARGS_LEN)
+            .map(|offset| &data[offset..])
+        else {
+            return Vec::new();
+        };
+        let in_amount = u64::from_le_bytes(args[..8].try_into().unwrap());
+        let quoted_out_amount = u64::from_le_bytes(args[8..16].try_into().unwrap());
+        let slippage_bps = u16::from_le_bytes(args[16..18].try_into().unwrap());
+
+        let account_key = |index: usize| account_key(account_keys, accounts, index);
+
+        let Some(user) = account_key(route.user_account) else {
+            return Vec::new();
+        };
+
+        // every leg's accounts start with the program of the amm it swaps on,
+        // which for raydium is followed by the token program and the amm id
+        let amm_ids = (route.remaining_accounts..accounts.len())
+            .filter(|index| account_key(*index) == Some(RAYDIUM_AMM_PROGRAM_ID))
+            .filter_map(|index| account_key(index + 2));
+
+        let Some(plan) = data
+            .get(route.plan_offset..data.len() - ROUTE_ARGS_LEN)
+            .and_then(parse_route_plan)
+        else {
+            // without the plan we can still say which pools the user touched
+            return amm_ids
+                .map(|amm_id| SwapInstruction {
+                    amount_in: None,
+                    minimum_amount_out: None,
+                    amm_id,
+                    user,
+                })
+                .collect();
+        };
+
+        let single_step = plan.len() == 1;
+
+        plan.iter()
+            .filter(|step| step.tag == RAYDIUM_SWAP_TAG)
+            .zip(amm_ids)
+            .map(|(step, amm_id)| {
+                let amount_in = (step.input_index == 0)
+                    .then(|| (u128::from(in_amount) * u128::from(step.percent) / 100) as u64);
+
+                let minimum_amount_out = single_step.then(|| {
+                    (u128::from(quoted_out_amount)
+                        * u128::from(10_000u16.saturating_sub(slippage_bps))
+                        / 10_000) as u64
+                });
+
+                SwapInstruction {
+                    amount_in,
+                    minimum_amount_out,
+                    amm_id,
+                    user,
+                }
+            })
+            .collect()
+    }
+}
+
+fn parse_route_plan(data: &[u8]) -> Option<Vec<RoutePlanStep>> {
+    let (len, mut rest) = data.split_first_chunk::<4>()?;
+    let len = u32::from_le_bytes(*len);
+
+    let mut steps = Vec::new();
+    for _ in 0..len {
+        let (&tag, after_tag) = rest.split_first()?;
+        let payload = swap_payload_len(tag)?;
+
+        let step = after_tag.get(payload..payload + 3)?;
+        steps.push(RoutePlanStep {
+            tag,
+            percent: step[0],
+            input_index: step[1],
+        });
+
+        rest = &after_tag[payload + 3..];
+    }
+
+    // anything left over means we misread a variant somewhere
+    rest.is_empty().then_some(steps)
+}
+
+// size of the fields carried by each variant of jupiter's `Swap` enum, newer
+// variants are not fixed size or not known yet and stop the plan from parsing
+fn swap_payload_len(tag: u8) -> Option<usize> {
+    Some(match tag {
+        // Crema, Serum, Aldrin, AldrinV2, Whirlpool, Invariant, DeltaFi,
+        // MarcoPolo, Dradex, Openbook, Phoenix, OpenBookV2
+        8 | 12 | 15 | 16 | 17 | 18 | 21 | 23 | 24 | 27 | 28 | 39 => 1,
+        // Symmetry
+        29 => 16,
+        // StakeDexSwapViaStake, StakeDexPrefundWithdrawStakeAndDepositStake
+        33 | 41 => 4,
+        // Clone
+        42 => 3,
+        // SanctumS
+        43 => 10,
+        // SanctumSAddLiquidity, SanctumSRemoveLiquidity
+        44 | 45 => 5,
+        0..=46 => 0,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHIRLPOOL_PROGRAM_ID: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+    const IN_AMOUNT: u64 = 2_000_000_000;
+    const QUOTED_OUT_AMOUNT: u64 = 300_000_000;
+    const SLIPPAGE_BPS: u16 = 50;
+
+    // laid out the way the Jupiter v6 IDL serializes the instruction:
+    // discriminator, shared accounts id, route plan, then the route args
+    fn instruction_data(
+        discriminator: [u8; 8],
+        id: Option<u8>,
+        plan: &[(&[u8], u8, u8, u8)],
+    ) -> Vec<u8> {
+        let mut data = discriminator.to_vec();
+        data.extend(id);
+        data.extend_from_slice(&(plan.len() as u32).to_le_bytes());
+        for (swap, percent, input_index, output_index) in plan {
+            data.extend_from_slice(swap);
+            data.extend([*percent, *input_index, *output_index]);
+        }
+        data.extend_from_slice(&IN_AMOUNT.to_le_bytes());
+        data.extend_from_slice(&QUOTED_OUT_AMOUNT.to_le_bytes());
+        data.extend_from_slice(&SLIPPAGE_BPS.to_le_bytes());
+        data.push(0);
+        data
+    }
+
+    /// Instruction accounts: the route's own, then every leg's accounts.
+    struct Accounts {
+        account_keys: Vec<Pubkey>,
+        accounts: Vec<u8>,
+    }
+
+    impl Accounts {
+        fn new(route_accounts: usize) -> Self {
+            let mut accounts = Self {
+                account_keys: Vec::new(),
+                accounts: Vec::new(),
+            };
+            for _ in 0..route_accounts {
+                accounts.push(Pubkey::new_unique());
+            }
+            accounts
+        }
+
+        fn push(&mut self, key: Pubkey) {
+            self.accounts.push(self.account_keys.len() as u8);
+            self.account_keys.push(key);
+        }
+
+        fn key(&self, index: usize) -> Pubkey {
+            self.account_keys[self.accounts[index] as usize]
+        }
+
+        /// Adds a Raydium leg and returns its amm id.
+        fn raydium_leg(&mut self) -> Pubkey {
+            let amm_id = Pubkey::new_unique();
+            self.push(RAYDIUM_AMM_PROGRAM_ID);
+            self.push(spl_token_2022::id());
+            self.push(amm_id);
+            for _ in 0..15 {
+                self.push(Pubkey::new_unique());
+            }
+            amm_id
+        }
+
+        fn whirlpool_leg(&mut self) {
+            self.push(WHIRLPOOL_PROGRAM_ID);
+            for _ in 0..10 {
+                self.push(Pubkey::new_unique());
+            }
+        }
+
+        fn decode(&self, data: &[u8]) -> Vec<(Option<u64>, Option<u64>, Pubkey, Pubkey)> {
+            Jupiter
+                .decode_swaps(data, &self.account_keys, &self.accounts)
+                .into_iter()
+                .map(|swap| {
+                    (
+                        swap.amount_in,
+                        swap.minimum_amount_out,
+                        swap.amm_id,
+                        swap.user,
+                    )
+                })
+                .collect()
+        }
+    }
+
+    const RAYDIUM: &[u8] = &[RAYDIUM_SWAP_TAG];
+    // Whirlpool { a_to_b: true }
+    const WHIRLPOOL: &[u8] = &[17, 1];
+    // Symmetry { from_token_id: 3, to_token_id: 5 }
+    const SYMMETRY: &[u8] = &[29, 3, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0];
+
+    #[test]
+    fn single_step_route() {
+        let mut accounts = Accounts::new(9);
+        let amm_id = accounts.raydium_leg();
+        let data = instruction_data(ROUTE_DISCRIMINATOR, None, &[(RAYDIUM, 100, 0, 1)]);
+
+        let minimum = QUOTED_OUT_AMOUNT * (10_000 - u64::from(SLIPPAGE_BPS)) / 10_000;
+        assert_eq!(
+            accounts.decode(&data),
+            [(Some(IN_AMOUNT), Some(minimum), amm_id, accounts.key(1))]
+        );
+    }
+
+    #[test]
+    fn split_route() {
+        let mut accounts = Accounts::new(9);
+        let first = accounts.raydium_leg();
+        let second = accounts.raydium_leg();
+        let data = instruction_data(
+            ROUTE_DISCRIMINATOR,
+            None,
+            &[(RAYDIUM, 60, 0, 1), (RAYDIUM, 40, 0, 1)],
+        );
+
+        let user = accounts.key(1);
+        assert_eq!(
+            accounts.decode(&data),
+            [
+                (Some(IN_AMOUNT * 60 / 100), None, first, user),
+                (Some(IN_AMOUNT * 40 / 100), None, second, user),
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_hop_shared_accounts_route() {
+        let mut accounts = Accounts::new(13);
+        accounts.whirlpool_leg();
+        let amm_id = accounts.raydium_leg();
+        let data = instruction_data(
+            SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR,
+            Some(4),
+            &[(WHIRLPOOL, 100, 0, 1), (RAYDIUM, 100, 1, 2)],
+        );
+
+        // the raydium leg spends what the whirlpool leg bought
+        assert_eq!(
+            accounts.decode(&data),
+            [(None, None, amm_id, accounts.key(2))]
+        );
+    }
+
+    #[test]
+    fn sized_variants_ahead_of_a_raydium_step() {
+        let mut accounts = Accounts::new(9);
+        let amm_id = accounts.raydium_leg();
+        let data = instruction_data(
+            ROUTE_DISCRIMINATOR,
+            None,
+            &[(SYMMETRY, 50, 0, 1), (RAYDIUM, 50, 0, 1)],
+        );
+
+        assert_eq!(
+            accounts.decode(&data),
+            [(Some(IN_AMOUNT / 2), None, amm_id, accounts.key(1))]
+        );
+    }
+
+    #[test]
+    fn unknown_variant_falls_back_to_the_pools() {
+        let mut accounts = Accounts::new(9);
+        let first = accounts.raydium_leg();
+        let second = accounts.raydium_leg();
+        let data = instruction_data(
+            ROUTE_DISCRIMINATOR,
+            None,
+            &[(RAYDIUM, 100, 0, 1), (&[99, 1, 2], 100, 1, 2)],
+        );
+
+        let user = accounts.key(1);
+        assert_eq!(
+            accounts.decode(&data),
+            [(None, None, first, user), (None, None, second, user)]
+        );
+    }
+
+    #[test]
+    fn other_instructions_are_not_swaps() {
+        let mut accounts = Accounts::new(9);
+        accounts.raydium_leg();
+        let mut data = instruction_data(ROUTE_DISCRIMINATOR, None, &[(RAYDIUM, 100, 0, 1)]);
+        data[0] ^= 1;
+
+        assert!(accounts.decode(&data).is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+use borsh::BorshDeserialize;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::serde_helpers::display;
+
+pub mod banana;
+pub mod jupiter;
+pub mod raydium_amm;
+
+#[derive(Debug, Serialize)]
+pub struct SwapInstruction {
+    /// `None` for route legs whose input is only known once an earlier leg
+    /// has run.
+    pub amount_in: Option<u64>,
+    /// `None` for route legs that are only checked against the route's
+    /// output.
+    pub minimum_amount_out: Option<u64>,
+
+    #[serde(serialize_with = "display")]
+    pub amm_id: Pubkey,
+    /// Wallet the swap is made for, not necessarily the program that made it.
+    #[serde(serialize_with = "display")]
+    pub user: Pubkey,
+}
+
+#[derive(BorshDeserialize)]
+struct SwapBaseIn {
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+}
+
+// key of the instruction's `index`th account, `None` when the instruction is
+// short of accounts or the account comes from a lookup table we don't have
+fn account_key(account_keys: &[Pubkey], accounts: &[u8], index: usize) -> Option<Pubkey> {
+    accounts
+        .get(index)
+        .and_then(|key_index| account_keys.get(*key_index as usize))
+        .copied()
+}
+
+pub trait InstructionDecoder {
+    fn name(&self) -> &'static str;
+
+    fn decode_instruction(
+        &self,
+        data: &[u8],
+        account_keys: &[Pubkey],
+        accounts: &[u8],
+    ) -> Option<SwapInstruction>;
+
+    fn decode_swaps(
+        &self,
+        data: &[u8],
+        account_keys: &[Pubkey],
+        accounts: &[u8],
+    ) -> Vec<SwapInstruction> {
+        self.decode_instruction(data, account_keys, accounts)
+            .into_iter()
+            .collect()
+    }
+}
+
+pub fn get_instruction_decoder(program_id: &Pubkey) -> Option<Box<dyn InstructionDecoder + Send>> {
+    match *program_id {
+        raydium_amm::RAYDIUM_AMM_PROGRAM_ID => Some(Box::new(raydium_amm::RaydiumAmm)),
+        banana::BANANA_PROGRAM_ID => Some(Box::new(banana::BananaGun)),
+        jupiter::JUPITER_PROGRAM_ID => Some(Box::new(jupiter::Jupiter)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap_base_in_data() -> Vec<u8> {
+        let mut data = vec![9];
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&900u64.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn raydium_amm_decodes_a_swap() {
+        let account_keys: Vec<Pubkey> = (0..18).map(|_| Pubkey::new_unique()).collect();
+        let accounts: Vec<u8> = (0..18).collect();
+
+        let swap = raydium_amm::RaydiumAmm
+            .decode_instruction(&swap_base_in_data(), &account_keys, &accounts)
+            .unwrap();
+
+        assert_eq!(swap.amount_in, Some(1_000));
+        assert_eq!(swap.minimum_amount_out, Some(900));
+        assert_eq!(swap.amm_id, account_keys[1]);
+        assert_eq!(swap.user, account_keys[17]);
+    }
+
+    #[test]
+    fn malformed_instructions_decode_to_nothing() {
+        let mut account_keys: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+        account_keys[3] = raydium_amm::RAYDIUM_AMM_PROGRAM_ID;
+        let accounts: Vec<u8> = (0..8).collect();
+        // accounts from a lookup table index past the static keys
+        let looked_up: Vec<u8> = (0..8).map(|index| index + 100).collect();
+
+        type Case<'a> = (&'a str, &'a dyn InstructionDecoder, Vec<u8>, &'a [u8]);
+        let cases: [Case; 8] = [
+            (
+                "raydium, no accounts",
+                &raydium_amm::RaydiumAmm,
+                swap_base_in_data(),
+                &[],
+            ),
+            (
+                "raydium, looked up accounts",
+                &raydium_amm::RaydiumAmm,
+                swap_base_in_data(),
+                &looked_up,
+            ),
+            (
+                "raydium, short data",
+                &raydium_amm::RaydiumAmm,
+                vec![9, 1, 2],
+                &accounts,
+            ),
+            (
+                "banana, no accounts",
+                &banana::BananaGun,
+                swap_base_in_data(),
+                &[],
+            ),
+            (
+                "banana, looked up accounts",
+                &banana::BananaGun,
+                swap_base_in_data(),
+                &looked_up,
+            ),
+            (
+                "banana, short data",
+                &banana::BananaGun,
+                vec![1, 2, 3],
+                &accounts,
+            ),
+            (
+                "jupiter, no accounts",
+                &jupiter::Jupiter,
+                vec![229, 23, 203, 151, 122, 227, 173, 42],
+                &[],
+            ),
+            (
+                "jupiter, empty data",
+                &jupiter::Jupiter,
+                Vec::new(),
+                &accounts,
+            ),
+        ];
+
+        for (name, decoder, data, accounts) in cases {
+            assert!(
+                decoder
+                    .decode_swaps(&data, &account_keys, accounts)
+                    .is_empty(),
+                "{name}"
+            );
+        }
+
+        // the same instruction decodes once its accounts are all there
+        assert!(banana::BananaGun
+            .decode_instruction(&swap_base_in_data()[1..], &account_keys, &accounts)
+            .is_some());
+    }
+}
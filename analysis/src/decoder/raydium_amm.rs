@@ -2,13 +2,19 @@ use borsh::BorshDeserialize;
 use solana_sdk::pubkey;
 use solana_sdk::pubkey::Pubkey;
 
-use super::{InstructionDecoder, SwapBaseIn, SwapInstruction};
+use super::{account_key, InstructionDecoder, SwapBaseIn, SwapInstruction};
 
 pub const RAYDIUM_AMM_PROGRAM_ID: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+// owner of every pool's vaults
+pub const RAYDIUM_AMM_AUTHORITY: Pubkey = pubkey!("5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1");
 
 pub struct RaydiumAmm;
 
 impl InstructionDecoder for RaydiumAmm {
+    fn name(&self) -> &'static str {
+        "raydium_amm"
+    }
+
     fn decode_instruction(
         &self,
         data: &[u8],
@@ -26,9 +32,11 @@ impl InstructionDecoder for RaydiumAmm {
         };
 
         Some(SwapInstruction {
-            amount_in: instruction.amount_in,
-            minimum_amount_out: instruction.minimum_amount_out,
-            amm_id: account_keys[accounts[1] as usize],
+            amount_in: Some(instruction.amount_in),
+            minimum_amount_out: Some(instruction.minimum_amount_out),
+            amm_id: account_key(account_keys, accounts, 1)?,
+            // the source token account owner is always the last account
+            user: account_key(account_keys, accounts, accounts.len().checked_sub(1)?)?,
         })
     }
 }
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use crate::{
+    decoder::SwapInstruction,
+    pool::Pool,
+    serde_helpers::display,
+    simulator::{simulate_sandwich, SandwichEstimate},
+};
+
+/// What a swap did to its pool according to confirmed transaction metadata.
+#[derive(Clone, Debug, Serialize)]
+pub struct Settlement {
+    #[serde(serialize_with = "display")]
+    pub mint_in: Pubkey,
+    #[serde(serialize_with = "display")]
+    pub mint_out: Pubkey,
+    /// What the trader paid, before the input's transfer fee.
+    pub amount_in: u64,
+    /// What the trader received, after the output's transfer fee.
+    pub amount_out: u64,
+    /// Reserves and transfer fees before the transaction, oriented like the
+    /// swap.
+    pub pool_before: Pool,
+}
+
+/// A decoded swap at its position in execution order.
+pub struct ObservedSwap {
+    pub signature: Signature,
+    pub swap: SwapInstruction,
+    pub settlement: Option<Settlement>,
+}
+
+/// Indices into the swaps passed to [`detect`].
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sandwich {
+    pub front: usize,
+    pub victims: Vec<usize>,
+    pub back: usize,
+}
+
+/// Finds swaps that are bracketed on the same pool by two swaps of a single
+/// other user.
+///
+/// From transaction bytes alone that is all we can go on. Swaps that carry a
+/// settlement also have to point the right way: victims sell what the
+/// front-run sold and the back-run sells it back.
+pub fn detect(swaps: &[ObservedSwap]) -> Vec<Sandwich> {
+    let mut sandwiches = Vec::new();
+
+    for (front, front_swap) in swaps.iter().enumerate() {
+        let same_pool = |swap: &ObservedSwap| swap.swap.amm_id == front_swap.swap.amm_id;
+
+        let Some(back) = swaps[front + 1..]
+            .iter()
+            .position(|swap| same_pool(swap) && swap.swap.user == front_swap.swap.user)
+            .map(|offset| front + 1 + offset)
+        else {
+            continue;
+        };
+
+        if !reverses(front_swap, &swaps[back]) {
+            continue;
+        }
+
+        let victims: Vec<usize> = (front + 1..back)
+            .filter(|victim| same_pool(&swaps[*victim]) && follows(front_swap, &swaps[*victim]))
+            .collect();
+
+        if victims.is_empty() {
+            continue;
+        }
+
+        sandwiches.push(Sandwich {
+            front,
+            victims,
+            back,
+        });
+    }
+
+    sandwiches
+}
+
+fn follows(front: &ObservedSwap, victim: &ObservedSwap) -> bool {
+    match (&front.settlement, &victim.settlement) {
+        (Some(front), Some(victim)) => front.mint_in == victim.mint_in,
+        _ => true,
+    }
+}
+
+fn reverses(front: &ObservedSwap, back: &ObservedSwap) -> bool {
+    match (&front.settlement, &back.settlement) {
+        (Some(front), Some(back)) => front.mint_out == back.mint_in,
+        _ => true,
+    }
+}
+
+/// A detection checked against what actually happened on chain.
+#[derive(Debug, Serialize)]
+pub struct Confirmation {
+    /// Constant product estimate from the decoded instruction amounts.
+    pub estimate: SandwichEstimate,
+    /// Victims' fair output at their settled input minus what they received.
+    pub realised_victim_loss: u64,
+    /// Back-run output minus front-run input as settled.
+    pub realised_attacker_profit: i128,
+    pub confirmed: bool,
+}
+
+/// Confirms a sandwich whose swaps all carry a settlement. It holds if the
+/// attacker made money, the victims lost some, and both figures are within
+/// `tolerance` of the estimate, as a fraction of the estimate.
+pub fn confirm(
+    swaps: &[ObservedSwap],
+    sandwich: &Sandwich,
+    tolerance: f64,
+) -> Option<Confirmation> {
+    let front = &swaps[sandwich.front];
+    let front_settlement = front.settlement.as_ref()?;
+    let back_settlement = swaps[sandwich.back].settlement.as_ref()?;
+
+    let victims = sandwich
+        .victims
+        .iter()
+        .map(|victim| {
+            let victim = &swaps[*victim];
+            Some((&victim.swap, victim.settlement.as_ref()?))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    // route legs decode without an input, fall back to what they settled
+    let decoded_amount_in = |swap: &SwapInstruction, settlement: &Settlement| {
+        swap.amount_in.unwrap_or(settlement.amount_in)
+    };
+
+    let estimate = simulate_sandwich(
+        front_settlement.pool_before,
+        decoded_amount_in(&front.swap, front_settlement),
+        &victims
+            .iter()
+            .map(|(swap, settlement)| decoded_amount_in(swap, settlement))
+            .collect::<Vec<_>>(),
+    );
+
+    let realised = simulate_sandwich(
+        front_settlement.pool_before,
+        front_settlement.amount_in,
+        &victims
+            .iter()
+            .map(|(_, settlement)| settlement.amount_in)
+            .collect::<Vec<_>>(),
+    );
+    let realised_victim_loss: u64 = realised
+        .victims
+        .iter()
+        .zip(&victims)
+        .map(|(fair, (_, settlement))| fair.fair_out.saturating_sub(settlement.amount_out))
+        .sum();
+
+    let realised_attacker_profit =
+        i128::from(back_settlement.amount_out) - i128::from(front_settlement.amount_in);
+
+    let confirmed = realised_attacker_profit > 0
+        && realised_victim_loss > 0
+        && within(
+            realised_attacker_profit,
+            estimate.attacker_profit,
+            tolerance,
+        )
+        && within(
+            realised_victim_loss.into(),
+            estimate.victim_loss().into(),
+            tolerance,
+        );
+
+    Some(Confirmation {
+        estimate,
+        realised_victim_loss,
+        realised_attacker_profit,
+        confirmed,
+    })
+}
+
+fn within(realised: i128, estimated: i128, tolerance: f64) -> bool {
+    (realised - estimated).abs() as f64 <= tolerance * estimated.abs().max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use spl_token_2022::extension::transfer_fee::TransferFee as EpochFee;
+
+    use super::*;
+    use crate::{
+        metadata::tests::{sandwich, sandwich_with_mints, USDC},
+        token::tests::mint_with_transfer_fee,
+    };
+
+    #[test]
+    fn detects_and_confirms_a_sandwich() {
+        let swaps = sandwich();
+
+        let sandwiches = detect(&swaps);
+        assert_eq!(
+            sandwiches,
+            [Sandwich {
+                front: 0,
+                victims: vec![1],
+                back: 2,
+            }]
+        );
+
+        let confirmation = confirm(&swaps, &sandwiches[0], 0.1).unwrap();
+        assert!(confirmation.confirmed);
+        assert_eq!(confirmation.realised_attacker_profit, 5_391_283_188);
+        assert!(confirmation.realised_victim_loss > 0);
+        // the decoded amounts are the settled ones, so the estimate is exact
+        assert_eq!(
+            confirmation.estimate.attacker_profit,
+            confirmation.realised_attacker_profit
+        );
+        assert_eq!(
+            confirmation.estimate.victim_loss(),
+            confirmation.realised_victim_loss
+        );
+    }
+
+    #[test]
+    fn transfer_fees_come_out_of_the_realised_profit() {
+        let fee = EpochFee {
+            epoch: 0.into(),
+            transfer_fee_basis_points: 100.into(),
+            maximum_fee: u64::MAX.into(),
+        };
+        let mints = HashMap::from([(USDC, mint_with_transfer_fee(fee, fee))]);
+        let swaps = sandwich_with_mints(&mints);
+
+        let confirmation = confirm(&swaps, &detect(&swaps)[0], 0.1).unwrap();
+        // 1% on the way in and 1% on the way out of 5_391_283_188 without fees
+        assert_eq!(confirmation.realised_attacker_profit, 1_317_168_335);
+        assert!(confirmation.confirmed);
+    }
+
+    #[test]
+    fn a_losing_back_run_is_not_confirmed() {
+        let mut swaps = sandwich();
+        let sandwich = detect(&swaps).remove(0);
+
+        let front_amount_in = swaps[0].settlement.as_ref().unwrap().amount_in;
+        swaps[2].settlement.as_mut().unwrap().amount_out = front_amount_in - 1;
+
+        let confirmation = confirm(&swaps, &sandwich, 0.1).unwrap();
+        assert!(!confirmation.confirmed);
+        assert_eq!(confirmation.realised_attacker_profit, -1);
+    }
+
+    #[test]
+    fn a_back_run_the_same_way_is_no_sandwich() {
+        let mut swaps = sandwich();
+
+        let settlement = swaps[2].settlement.as_mut().unwrap();
+        std::mem::swap(&mut settlement.mint_in, &mut settlement.mint_out);
+
+        assert!(detect(&swaps).is_empty());
+    }
+
+    #[test]
+    fn unsettled_swaps_cannot_be_confirmed() {
+        let mut swaps = sandwich();
+        let sandwich = detect(&swaps).remove(0);
+
+        swaps[1].settlement = None;
+
+        assert!(confirm(&swaps, &sandwich, 0.1).is_none());
+    }
+}
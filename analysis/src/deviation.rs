@@ -0,0 +1,178 @@
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::serde_helpers::display;
+
+/// Price of `mint` in `quote_mint` implied by one pool's reserves, in raw
+/// token units.
+///
+/// The pair is ordered by mint address rather than by the pool's base and
+/// quote, so pools listing the same pair either way round compare directly.
+#[derive(Clone, Debug, Serialize)]
+pub struct PoolPrice {
+    #[serde(serialize_with = "display")]
+    pub amm_id: Pubkey,
+    #[serde(serialize_with = "display")]
+    pub mint: Pubkey,
+    #[serde(serialize_with = "display")]
+    pub quote_mint: Pubkey,
+    pub price: f64,
+}
+
+impl PoolPrice {
+    /// `None` for an empty pool, which has no price.
+    pub fn new(
+        amm_id: Pubkey,
+        (base_mint, base_reserve): (Pubkey, u64),
+        (quote_mint, quote_reserve): (Pubkey, u64),
+    ) -> Option<Self> {
+        if base_reserve == 0 || quote_reserve == 0 {
+            return None;
+        }
+
+        let ((mint, reserve), (quote_mint, quote_reserve)) = if base_mint < quote_mint {
+            ((base_mint, base_reserve), (quote_mint, quote_reserve))
+        } else {
+            ((quote_mint, quote_reserve), (base_mint, base_reserve))
+        };
+
+        Some(Self {
+            amm_id,
+            mint,
+            quote_mint,
+            price: quote_reserve as f64 / reserve as f64,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Deviation {
+    #[serde(flatten)]
+    pub pool: PoolPrice,
+    /// Median price of every pool trading the pair.
+    pub reference_price: f64,
+    pub deviation_bps: f64,
+}
+
+/// Compares every pool's price with the median over the pools trading the
+/// same pair and returns those further off it than `threshold_bps`. Pairs
+/// with fewer than three pools are skipped: two pools sit either side of
+/// their median, so both or neither would be flagged.
+pub fn deviations(prices: &[PoolPrice], threshold_bps: f64) -> Vec<Deviation> {
+    let mut deviations = Vec::new();
+
+    let mut pairs: Vec<(Pubkey, Pubkey)> = prices
+        .iter()
+        .map(|price| (price.mint, price.quote_mint))
+        .collect();
+    pairs.sort();
+    pairs.dedup();
+
+    for pair in pairs {
+        let pair_prices: Vec<&PoolPrice> = prices
+            .iter()
+            .filter(|price| (price.mint, price.quote_mint) == pair)
+            .collect();
+        if pair_prices.len() < 3 {
+            continue;
+        }
+
+        let reference_price = median(pair_prices.iter().map(|price| price.price).collect());
+
+        for price in pair_prices {
+            let deviation_bps = (price.price / reference_price - 1.0) * 10_000.0;
+            if deviation_bps.abs() > threshold_bps {
+                deviations.push(Deviation {
+                    pool: price.clone(),
+                    reference_price,
+                    deviation_bps,
+                });
+            }
+        }
+    }
+
+    deviations
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(amm_id: Pubkey, mint: Pubkey, quote_mint: Pubkey, price: f64) -> PoolPrice {
+        PoolPrice {
+            amm_id,
+            mint,
+            quote_mint,
+            price,
+        }
+    }
+
+    #[test]
+    fn pairs_are_ordered_by_mint() {
+        let (amm_id, low, high) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        assert!(low < high);
+
+        let listed = PoolPrice::new(amm_id, (low, 100), (high, 250)).unwrap();
+        let flipped = PoolPrice::new(amm_id, (high, 250), (low, 100)).unwrap();
+
+        for price in [listed, flipped] {
+            assert_eq!((price.mint, price.quote_mint), (low, high));
+            assert_eq!(price.price, 2.5);
+        }
+
+        assert!(PoolPrice::new(amm_id, (low, 0), (high, 250)).is_none());
+        assert!(PoolPrice::new(amm_id, (low, 100), (high, 0)).is_none());
+    }
+
+    #[test]
+    fn median_of_odd_and_even_counts() {
+        assert_eq!(median(vec![3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(vec![4.0, 1.0, 3.0, 2.0]), 2.5);
+        assert_eq!(median(vec![7.0]), 7.0);
+    }
+
+    #[test]
+    fn flags_pools_off_their_pairs_median() {
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let pools: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+
+        let prices = [
+            price(pools[0], a, b, 100.0),
+            price(pools[1], a, b, 100.5),
+            price(pools[2], a, b, 90.0),
+            // the only pool of its pair
+            price(pools[3], a, c, 1.0),
+            price(pools[4], a, b, 99.5),
+        ];
+
+        let flagged = deviations(&prices, 100.0);
+
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].pool.amm_id, pools[2]);
+        // median of 90, 99.5, 100 and 100.5
+        assert_eq!(flagged[0].reference_price, 99.75);
+        assert!((flagged[0].deviation_bps + 977.4436).abs() < 1e-3);
+
+        // two pools can't tell which of them is off, however far apart
+        assert!(deviations(&prices[1..3], 0.0).is_empty());
+    }
+}
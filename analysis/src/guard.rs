@@ -0,0 +1,81 @@
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+
+const GUARD_TAG: u8 = 3;
+
+/// A swap through a Raydium AMM v4 pool as quoted to the user.
+#[derive(Clone, Copy, Debug)]
+pub struct Quote {
+    pub amm_id: Pubkey,
+    /// Pool vault of the token being sold.
+    pub pool_in_token_account: Pubkey,
+    /// Pool vault of the token being bought.
+    pub pool_out_token_account: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+/// Builds the guard instruction that goes in front of a user's swap, so the
+/// transaction fails if the pool moved against the quote by more than
+/// `tolerance_bps` by the time it lands.
+pub struct GuardBuilder {
+    pub program_id: Pubkey,
+    pub quote: Quote,
+    pub tolerance_bps: u16,
+}
+
+impl GuardBuilder {
+    pub fn new(program_id: Pubkey, quote: Quote, tolerance_bps: u16) -> Self {
+        Self {
+            program_id,
+            quote,
+            tolerance_bps,
+        }
+    }
+
+    pub fn guard_instruction(&self) -> Instruction {
+        let accounts = vec![
+            AccountMeta::new_readonly(self.quote.amm_id, false),
+            AccountMeta::new_readonly(self.quote.pool_in_token_account, false),
+            AccountMeta::new_readonly(self.quote.pool_out_token_account, false),
+        ];
+
+        let mut data: Vec<u8> = Vec::new();
+        data.push(GUARD_TAG);
+        data.extend_from_slice(&self.quote.amount_in.to_le_bytes());
+        data.extend_from_slice(&self.quote.amount_out.to_le_bytes());
+        data.extend_from_slice(&self.tolerance_bps.to_le_bytes());
+
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        }
+    }
+
+    /// The user's swap instructions with the guard in front of them.
+    pub fn prepend(&self, swap_instructions: &[Instruction]) -> Vec<Instruction> {
+        let mut instructions = vec![self.guard_instruction()];
+        instructions.extend_from_slice(swap_instructions);
+        instructions
+    }
+
+    /// An unsigned transaction for the wallet to sign.
+    pub fn transaction(
+        &self,
+        swap_instructions: &[Instruction],
+        payer: &Pubkey,
+        recent_blockhash: Hash,
+    ) -> Transaction {
+        Transaction::new_unsigned(Message::new_with_blockhash(
+            &self.prepend(swap_instructions),
+            Some(payer),
+            &recent_blockhash,
+        ))
+    }
+}
@@ -0,0 +1,13 @@
+pub mod advisor;
+pub mod benchmark;
+pub mod decoder;
+pub mod detector;
+pub mod deviation;
+pub mod guard;
+pub mod metadata;
+pub mod pool;
+pub mod pool_stats;
+pub mod reorder;
+pub mod serde_helpers;
+pub mod simulator;
+pub mod token;
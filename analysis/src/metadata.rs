@@ -0,0 +1,373 @@
+use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
+
+use anyhow::{anyhow, Result};
+use solana_sdk::{epoch_schedule::EpochSchedule, pubkey::Pubkey};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta, UiInstruction,
+    UiTransactionTokenBalance,
+};
+
+use crate::{
+    decoder::{get_instruction_decoder, raydium_amm::RAYDIUM_AMM_AUTHORITY, SwapInstruction},
+    detector::{ObservedSwap, Settlement},
+    pool::{Pool, TransferFee},
+    token,
+};
+
+/// Reads a JSON array of `getTransaction` results, fetched with base64
+/// encoding and in execution order, into the swaps they made.
+///
+/// Swaps are decoded from the transaction bytes as usual. The metadata adds
+/// swaps made through programs we have no decoder for, via their inner
+/// instructions, and settles every swap from its pool's vault balances.
+///
+/// `mints` holds the account data of mints that may charge a transfer fee,
+/// by address. Mints missing from it are taken to charge none.
+pub fn load(path: &Path, mints: &HashMap<Pubkey, Vec<u8>>) -> Result<Vec<ObservedSwap>> {
+    let transactions: Vec<EncodedConfirmedTransactionWithStatusMeta> =
+        serde_json::from_reader(BufReader::new(File::open(path)?))?;
+
+    let mut swaps = Vec::new();
+    for transaction in transactions {
+        observe(transaction, mints, &mut swaps)?;
+    }
+
+    Ok(swaps)
+}
+
+struct Instruction {
+    program_id_index: u8,
+    accounts: Vec<u8>,
+    data: Vec<u8>,
+}
+
+fn observe(
+    transaction: EncodedConfirmedTransactionWithStatusMeta,
+    mints: &HashMap<Pubkey, Vec<u8>>,
+    swaps: &mut Vec<ObservedSwap>,
+) -> Result<()> {
+    let epoch = EpochSchedule::without_warmup().get_epoch(transaction.slot);
+    let transaction = transaction.transaction;
+
+    let versioned = transaction
+        .transaction
+        .decode()
+        .ok_or_else(|| anyhow!("transaction is not base64 encoded"))?;
+    let meta = transaction
+        .meta
+        .ok_or_else(|| anyhow!("transaction has no metadata"))?;
+
+    // a failed transaction moved nothing
+    if meta.err.is_some() {
+        return Ok(());
+    }
+
+    let signature = *versioned
+        .signatures
+        .first()
+        .ok_or_else(|| anyhow!("transaction has no signature"))?;
+
+    let mut account_keys = versioned.message.static_account_keys().to_vec();
+    if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+        for key in loaded.writable.iter().chain(&loaded.readonly) {
+            account_keys.push(key.parse()?);
+        }
+    }
+
+    let pre_balances = token_balances(meta.pre_token_balances)?;
+    let post_balances = token_balances(meta.post_token_balances)?;
+
+    let mut inner_instructions: HashMap<u8, Vec<Instruction>> = HashMap::new();
+    if let OptionSerializer::Some(inner) = meta.inner_instructions {
+        for inner in inner {
+            let instructions = inner
+                .instructions
+                .into_iter()
+                .filter_map(|instruction| match instruction {
+                    UiInstruction::Compiled(compiled) => Some(compiled),
+                    UiInstruction::Parsed(_) => None,
+                })
+                .map(|compiled| {
+                    Ok(Instruction {
+                        program_id_index: compiled.program_id_index,
+                        accounts: compiled.accounts,
+                        data: bs58::decode(compiled.data).into_vec()?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            inner_instructions.insert(inner.index, instructions);
+        }
+    }
+
+    let transfer_fees = mints
+        .iter()
+        .map(|(mint, data)| Ok((*mint, token::transfer_fee(data, epoch)?)))
+        .collect::<Result<HashMap<_, _>>>()?;
+
+    let settle =
+        |accounts: &[u8]| settlement(accounts, &pre_balances, &post_balances, &transfer_fees);
+
+    for (index, instruction) in versioned.message.instructions().iter().enumerate() {
+        let inner_swaps: Vec<(SwapInstruction, &[u8])> = inner_instructions
+            .get(&(index as u8))
+            .into_iter()
+            .flatten()
+            .flat_map(|inner| {
+                decode_swaps(&account_keys, inner)
+                    .into_iter()
+                    .map(|swap| (swap, inner.accounts.as_slice()))
+            })
+            .collect();
+
+        let outer_swaps = decode_swaps(
+            &account_keys,
+            &Instruction {
+                program_id_index: instruction.program_id_index,
+                accounts: instruction.accounts.clone(),
+                data: instruction.data.clone(),
+            },
+        );
+
+        if outer_swaps.is_empty() {
+            // swaps through a program we can't decode, like a sandwich program
+            for (swap, accounts) in inner_swaps {
+                swaps.push(ObservedSwap {
+                    signature,
+                    settlement: settle(accounts),
+                    swap,
+                });
+            }
+            continue;
+        }
+
+        for mut swap in outer_swaps {
+            // the outer instruction knows who the swap is for, the raydium
+            // call it makes knows the amounts and touches only one pool
+            let inner = inner_swaps
+                .iter()
+                .find(|(inner, _)| inner.amm_id == swap.amm_id);
+
+            let accounts = match inner {
+                Some((inner, accounts)) => {
+                    swap.amount_in = swap.amount_in.or(inner.amount_in);
+                    accounts
+                }
+                None => instruction.accounts.as_slice(),
+            };
+
+            swaps.push(ObservedSwap {
+                signature,
+                settlement: settle(accounts),
+                swap,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn decode_swaps(account_keys: &[Pubkey], instruction: &Instruction) -> Vec<SwapInstruction> {
+    let Some(program_id) = account_keys.get(instruction.program_id_index as usize) else {
+        return Vec::new();
+    };
+
+    match get_instruction_decoder(program_id) {
+        Some(decoder) => {
+            decoder.decode_swaps(&instruction.data, account_keys, &instruction.accounts)
+        }
+        None => Vec::new(),
+    }
+}
+
+struct TokenBalance {
+    mint: Pubkey,
+    owner: Option<Pubkey>,
+    amount: u64,
+}
+
+fn token_balances(
+    balances: OptionSerializer<Vec<UiTransactionTokenBalance>>,
+) -> Result<HashMap<u8, TokenBalance>> {
+    Option::<Vec<_>>::from(balances)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|balance| {
+            let owner = Option::<String>::from(balance.owner)
+                .map(|owner| owner.parse())
+                .transpose()?;
+
+            Ok((
+                balance.account_index,
+                TokenBalance {
+                    mint: balance.mint.parse()?,
+                    owner,
+                    amount: balance.ui_token_amount.amount.parse()?,
+                },
+            ))
+        })
+        .collect()
+}
+
+// the two raydium vaults among the instruction's accounts, one of which the
+// swap paid into and the other it was paid out of
+fn settlement(
+    accounts: &[u8],
+    pre_balances: &HashMap<u8, TokenBalance>,
+    post_balances: &HashMap<u8, TokenBalance>,
+    transfer_fees: &HashMap<Pubkey, TransferFee>,
+) -> Option<Settlement> {
+    let mut vaults = accounts.iter().filter(|index| {
+        pre_balances
+            .get(index)
+            .is_some_and(|balance| balance.owner == Some(RAYDIUM_AMM_AUTHORITY))
+    });
+
+    let (first, second) = (vaults.next()?, vaults.next()?);
+    if vaults.any(|index| index != first && index != second) {
+        return None;
+    }
+
+    let delta = |index: &u8| {
+        Some(i128::from(post_balances.get(index)?.amount) - i128::from(pre_balances[index].amount))
+    };
+    let (vault_in, vault_out) = match (delta(first)?, delta(second)?) {
+        (d1, d2) if d1 > 0 && d2 < 0 => (first, second),
+        (d1, d2) if d1 < 0 && d2 > 0 => (second, first),
+        _ => return None,
+    };
+
+    let (mint_in, mint_out) = (pre_balances[vault_in].mint, pre_balances[vault_out].mint);
+    let transfer_fee = |mint| transfer_fees.get(mint).copied().unwrap_or_default();
+    let pool_before = Pool::raydium(
+        pre_balances[vault_in].amount,
+        pre_balances[vault_out].amount,
+    )
+    .with_transfer_fees(transfer_fee(&mint_in), transfer_fee(&mint_out));
+
+    // the vaults only see what's left of either transfer after its fee
+    Some(Settlement {
+        mint_in,
+        mint_out,
+        amount_in: pool_before
+            .transfer_fee_in
+            .before_fee(delta(vault_in)? as u64),
+        amount_out: pool_before
+            .transfer_fee_out
+            .after_fee((-delta(vault_out)?) as u64),
+        pool_before,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use solana_sdk::pubkey;
+    use spl_token_2022::extension::transfer_fee::TransferFee as EpochFee;
+
+    use super::*;
+    use crate::token::tests::mint_with_transfer_fee;
+
+    const SOL: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+    pub(crate) const USDC: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    // Front-run, victim and back-run through the SOL-USDC pool, each a direct
+    // Raydium swap, in the shape `getTransaction` returns them with base64
+    // encoding. Assembled from a simulated sandwich on a 40k SOL / 6M USDC
+    // pool rather than captured from mainnet.
+    pub(crate) fn sandwich() -> Vec<ObservedSwap> {
+        sandwich_with_mints(&HashMap::new())
+    }
+
+    pub(crate) fn sandwich_with_mints(mints: &HashMap<Pubkey, Vec<u8>>) -> Vec<ObservedSwap> {
+        load(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/sandwich.json"),
+            mints,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn settles_swaps_from_vault_balances() {
+        let swaps = sandwich();
+        assert_eq!(swaps.len(), 3);
+
+        let settlements: Vec<&Settlement> = swaps
+            .iter()
+            .map(|swap| swap.settlement.as_ref().unwrap())
+            .collect();
+
+        // paid in USDC and out in SOL, with the reserves seen the same way
+        let front = settlements[0];
+        assert_eq!((front.mint_in, front.mint_out), (USDC, SOL));
+        assert_eq!(front.amount_in, 200_000_000_000);
+        assert_eq!(front.amount_out, 1_287_200_580_691);
+        assert_eq!(front.pool_before.reserve_in, 6_000_000_000_000);
+        assert_eq!(front.pool_before.reserve_out, 40_000_000_000_000);
+        assert_eq!(
+            front.amount_out,
+            front.pool_before.amount_out(front.amount_in)
+        );
+
+        let victim = settlements[1];
+        assert_eq!((victim.mint_in, victim.mint_out), (USDC, SOL));
+        assert_eq!(victim.pool_before.reserve_in, 6_200_000_000_000);
+
+        // the back-run sells what the front-run bought
+        let back = settlements[2];
+        assert_eq!((back.mint_in, back.mint_out), (SOL, USDC));
+        assert_eq!(back.amount_in, front.amount_out);
+        assert_eq!(
+            back.pool_before.reserve_in,
+            40_000_000_000_000 - front.amount_out - victim.amount_out
+        );
+    }
+
+    #[test]
+    fn decodes_who_swapped() {
+        let swaps = sandwich();
+
+        assert_eq!(swaps[0].swap.user, swaps[2].swap.user);
+        assert_ne!(swaps[0].swap.user, swaps[1].swap.user);
+        assert!(swaps
+            .iter()
+            .all(|swap| swap.swap.amm_id == swaps[0].swap.amm_id));
+        assert_eq!(swaps[1].swap.minimum_amount_out, Some(600_000_000_000));
+    }
+
+    #[test]
+    fn settles_through_transfer_fees() {
+        let fee = |basis_points: u16| EpochFee {
+            epoch: 0.into(),
+            transfer_fee_basis_points: basis_points.into(),
+            maximum_fee: u64::MAX.into(),
+        };
+        // as if USDC charged 1% on every transfer
+        let mints = HashMap::from([(USDC, mint_with_transfer_fee(fee(100), fee(100)))]);
+
+        let without_fees = sandwich();
+        let swaps = sandwich_with_mints(&mints);
+
+        // the vaults took in the same, so the front-run paid more for it
+        let front = swaps[0].settlement.as_ref().unwrap();
+        assert_eq!(front.pool_before.transfer_fee_in.basis_points, 100);
+        assert_eq!(front.amount_in, 202_020_202_021);
+        assert_eq!(
+            front.amount_out,
+            without_fees[0].settlement.as_ref().unwrap().amount_out
+        );
+        assert_eq!(
+            front.amount_out,
+            front.pool_before.amount_out(front.amount_in)
+        );
+
+        // and the back-run got less out of them
+        let back = swaps[2].settlement.as_ref().unwrap();
+        let back_without_fees = without_fees[2].settlement.as_ref().unwrap();
+        assert_eq!(back.pool_before.transfer_fee_out.basis_points, 100);
+        assert_eq!(
+            back.amount_out,
+            back_without_fees.amount_out - back_without_fees.amount_out.div_ceil(100)
+        );
+        assert_eq!(back.amount_out, back.pool_before.amount_out(back.amount_in));
+    }
+}
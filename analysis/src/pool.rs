@@ -0,0 +1,117 @@
+use sandy_layout::math;
+use serde::Serialize;
+
+/// A constant product pool seen from one side of a trade: `amount_in` is
+/// added to `reserve_in` and the output is taken from `reserve_out`.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Pool {
+    pub reserve_in: u64,
+    pub reserve_out: u64,
+    pub fee_numerator: u64,
+    pub fee_denominator: u64,
+    /// Taken from the input on its way into the pool.
+    pub transfer_fee_in: TransferFee,
+    /// Taken from the output on its way out of the pool.
+    pub transfer_fee_out: TransferFee,
+}
+
+impl Pool {
+    /// Raydium AMM v4 pools all charge 25 bps.
+    pub fn raydium(reserve_in: u64, reserve_out: u64) -> Self {
+        Self {
+            reserve_in,
+            reserve_out,
+            fee_numerator: 25,
+            fee_denominator: 10_000,
+            transfer_fee_in: TransferFee::default(),
+            transfer_fee_out: TransferFee::default(),
+        }
+    }
+
+    pub fn with_transfer_fees(
+        self,
+        transfer_fee_in: TransferFee,
+        transfer_fee_out: TransferFee,
+    ) -> Self {
+        Self {
+            transfer_fee_in,
+            transfer_fee_out,
+            ..self
+        }
+    }
+
+    /// What the trader receives for `amount_in`, after both transfer fees.
+    pub fn amount_out(&self, amount_in: u64) -> u64 {
+        let pool_amount_out = self.pool_amount_out(self.transfer_fee_in.after_fee(amount_in));
+        self.transfer_fee_out.after_fee(pool_amount_out)
+    }
+
+    // on what actually reaches the pool
+    fn pool_amount_out(&self, pool_amount_in: u64) -> u64 {
+        math::amount_out(
+            pool_amount_in,
+            self.reserve_in,
+            self.reserve_out,
+            self.fee_numerator,
+            self.fee_denominator,
+        )
+    }
+
+    /// Swaps `amount_in` through the pool, moving its reserves.
+    pub fn swap(&mut self, amount_in: u64) -> u64 {
+        let pool_amount_in = self.transfer_fee_in.after_fee(amount_in);
+        let pool_amount_out = self.pool_amount_out(pool_amount_in);
+
+        self.reserve_in = self.reserve_in.saturating_add(pool_amount_in);
+        self.reserve_out -= pool_amount_out;
+
+        self.transfer_fee_out.after_fee(pool_amount_out)
+    }
+
+    /// The same pool seen from the other side.
+    pub fn reversed(&self) -> Self {
+        Self {
+            reserve_in: self.reserve_out,
+            reserve_out: self.reserve_in,
+            transfer_fee_in: self.transfer_fee_out,
+            transfer_fee_out: self.transfer_fee_in,
+            ..*self
+        }
+    }
+}
+
+/// A Token-2022 transfer fee, zero for mints without the extension.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TransferFee {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+impl TransferFee {
+    // rounded up and capped like the token program does
+    pub fn fee(&self, amount: u64) -> u64 {
+        let fee = (u128::from(amount) * u128::from(self.basis_points)).div_ceil(10_000);
+        fee.min(u128::from(self.maximum_fee)) as u64
+    }
+
+    pub fn after_fee(&self, amount: u64) -> u64 {
+        amount - self.fee(amount)
+    }
+
+    /// Smallest transfer that leaves `amount` once the fee is taken.
+    pub fn before_fee(&self, amount: u64) -> u64 {
+        // what's left never falls as the transfer grows, and the cap puts the
+        // answer within `maximum_fee` of `amount`
+        let (mut lb, mut ub) = (amount, amount.saturating_add(self.maximum_fee));
+        while lb < ub {
+            let mid = lb + (ub - lb) / 2;
+            if self.after_fee(mid) >= amount {
+                ub = mid;
+            } else {
+                lb = mid + 1;
+            }
+        }
+
+        lb
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::{anyhow, Result};
+use sandy_layout::RaydiumMarketV4;
+
+/// Activity of a pool between two snapshots of its account, taken from the
+/// cumulative counters Raydium keeps in `RaydiumMarketV4`.
+///
+/// Raydium charges the swap fee on `amount_in`, so `base_to_quote_fee` is
+/// denominated in the base token and `quote_to_base_fee` in the quote token.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub base_in: u128,
+    pub quote_out: u128,
+    pub base_to_quote_fee: u64,
+
+    pub quote_in: u128,
+    pub base_out: u128,
+    pub quote_to_base_fee: u64,
+
+    pub base_total_pnl: u64,
+    pub quote_total_pnl: u64,
+}
+
+impl PoolStats {
+    pub fn between(before: &RaydiumMarketV4, after: &RaydiumMarketV4) -> Result<Self> {
+        // pools of the same pair share mints but never vaults
+        if before.base_vault != after.base_vault || before.quote_vault != after.quote_vault {
+            return Err(anyhow!("snapshots belong to different pools"));
+        }
+
+        Ok(Self {
+            base_in: delta(
+                before.swap_base_in_amount.get(),
+                after.swap_base_in_amount.get(),
+            )?,
+            quote_out: delta(
+                before.swap_quote_out_amount.get(),
+                after.swap_quote_out_amount.get(),
+            )?,
+            base_to_quote_fee: delta(before.swap_base2_quote_fee, after.swap_base2_quote_fee)?,
+
+            quote_in: delta(
+                before.swap_quote_in_amount.get(),
+                after.swap_quote_in_amount.get(),
+            )?,
+            base_out: delta(
+                before.swap_base_out_amount.get(),
+                after.swap_base_out_amount.get(),
+            )?,
+            quote_to_base_fee: delta(before.swap_quote2_base_fee, after.swap_quote2_base_fee)?,
+
+            base_total_pnl: delta(before.base_total_pnl, after.base_total_pnl)?,
+            quote_total_pnl: delta(before.quote_total_pnl, after.quote_total_pnl)?,
+        })
+    }
+
+    pub fn base_volume(&self) -> u128 {
+        self.base_in + self.base_out
+    }
+
+    pub fn quote_volume(&self) -> u128 {
+        self.quote_in + self.quote_out
+    }
+}
+
+// the counters only ever grow, so a decrease means the snapshots are swapped
+fn delta<T>(before: T, after: T) -> Result<T>
+where
+    T: std::ops::Sub<Output = T> + PartialOrd + Copy,
+{
+    if after < before {
+        return Err(anyhow!("counter decreased, are the snapshots in order?"));
+    }
+    Ok(after - before)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+
+    fn snapshot(base_vault: Pubkey, quote_vault: Pubkey) -> RaydiumMarketV4 {
+        RaydiumMarketV4 {
+            base_vault,
+            quote_vault,
+            ..RaydiumMarketV4::read(&[0; RaydiumMarketV4::LEN]).unwrap()
+        }
+    }
+
+    #[test]
+    fn diffs_the_counters() {
+        let (base_vault, quote_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let before = RaydiumMarketV4 {
+            swap_base_in_amount: 200.into(),
+            ..snapshot(base_vault, quote_vault)
+        };
+        let after = RaydiumMarketV4 {
+            swap_base_in_amount: 1_000.into(),
+            swap_quote_out_amount: 150.into(),
+            swap_base2_quote_fee: 3,
+            swap_quote_in_amount: 300.into(),
+            swap_base_out_amount: 1_990.into(),
+            swap_quote2_base_fee: 1,
+            base_total_pnl: 7,
+            quote_total_pnl: 2,
+            ..before
+        };
+
+        let stats = PoolStats::between(&before, &after).unwrap();
+
+        assert_eq!(
+            stats,
+            PoolStats {
+                base_in: 800,
+                quote_out: 150,
+                base_to_quote_fee: 3,
+                quote_in: 300,
+                base_out: 1_990,
+                quote_to_base_fee: 1,
+                base_total_pnl: 7,
+                quote_total_pnl: 2,
+            }
+        );
+        assert_eq!(stats.base_volume(), 2_790);
+        assert_eq!(stats.quote_volume(), 450);
+    }
+
+    #[test]
+    fn rejects_snapshots_out_of_order() {
+        let (base_vault, quote_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let before = RaydiumMarketV4 {
+            swap_quote_in_amount: 10.into(),
+            ..snapshot(base_vault, quote_vault)
+        };
+        let after = snapshot(base_vault, quote_vault);
+
+        assert!(PoolStats::between(&before, &after).is_err());
+        assert!(PoolStats::between(&after, &before).is_ok());
+        assert_eq!(delta(5u64, 5).unwrap(), 0);
+        assert!(delta(6u64, 5).is_err());
+    }
+
+    #[test]
+    fn rejects_snapshots_of_another_pool_of_the_pair() {
+        let (base_vault, quote_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let pool = snapshot(base_vault, quote_vault);
+        // same mints, other vaults
+        let other_pool = snapshot(Pubkey::new_unique(), quote_vault);
+
+        assert!(PoolStats::between(&pool, &other_pool).is_err());
+    }
+}
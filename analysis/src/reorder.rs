@@ -0,0 +1,245 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use log::warn;
+
+/// Where a feed says a packet executed. Any of it may be missing.
+pub trait Placement {
+    fn slot(&self) -> Option<u64>;
+    /// Index of the transaction within its slot.
+    fn position(&self) -> Option<u64>;
+    /// When the source saw the transaction, in microseconds since the epoch.
+    fn timestamp_us(&self) -> Option<u64>;
+}
+
+/// The packets of one slot in the order they executed, as far as the feed
+/// lets us tell.
+pub struct Block<P> {
+    /// `None` for packets the feed sent without a slot.
+    pub slot: Option<u64>,
+    pub packets: Vec<P>,
+}
+
+struct Arrival<P> {
+    sequence: u64,
+    packet: P,
+}
+
+/// Rebuilds the order of transactions within a slot from a feed that may
+/// deliver them late, out of order and spread over several batches.
+///
+/// A slot is held until a packet more than `lag` slots past it arrives, then
+/// released sorted by position. Packets without a position go after the
+/// rest, by timestamp and then by arrival.
+pub struct Reorderer<P> {
+    lag: u64,
+    pending: BTreeMap<u64, Vec<Arrival<P>>>,
+    next_sequence: u64,
+    /// Every slot below this one has been released.
+    released_below: u64,
+}
+
+impl<P: Placement> Reorderer<P> {
+    pub fn new(lag: u64) -> Self {
+        Self {
+            lag,
+            pending: BTreeMap::new(),
+            next_sequence: 0,
+            released_below: 0,
+        }
+    }
+
+    /// Takes the next packet off the feed and returns the blocks it
+    /// completes, oldest first.
+    pub fn push(&mut self, packet: P) -> Vec<Block<P>> {
+        let Some(slot) = packet.slot() else {
+            // nothing to place it by
+            return vec![Block {
+                slot: None,
+                packets: vec![packet],
+            }];
+        };
+
+        if slot < self.released_below {
+            warn!("Packet for slot {slot} arrived after the slot was released");
+            return vec![Block {
+                slot: Some(slot),
+                packets: vec![packet],
+            }];
+        }
+
+        self.pending.entry(slot).or_default().push(Arrival {
+            sequence: self.next_sequence,
+            packet,
+        });
+        self.next_sequence += 1;
+
+        self.release_below(slot.saturating_sub(self.lag))
+    }
+
+    /// Releases every slot still held, at the end of the feed.
+    pub fn flush(&mut self) -> Vec<Block<P>> {
+        self.release_below(u64::MAX)
+    }
+
+    fn release_below(&mut self, slot: u64) -> Vec<Block<P>> {
+        if slot <= self.released_below {
+            return Vec::new();
+        }
+        self.released_below = slot;
+
+        let held = self.pending.split_off(&slot);
+        let released = std::mem::replace(&mut self.pending, held);
+
+        released
+            .into_iter()
+            .map(|(slot, mut arrivals)| {
+                arrivals.sort_by(execution_order);
+                Block {
+                    slot: Some(slot),
+                    packets: arrivals.into_iter().map(|arrival| arrival.packet).collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+fn execution_order<P: Placement>(a: &Arrival<P>, b: &Arrival<P>) -> Ordering {
+    let key = |arrival: &Arrival<P>| {
+        (
+            // `None` sorts first, flip it so packets without one go last
+            arrival
+                .packet
+                .position()
+                .map_or((1, 0), |position| (0, position)),
+            arrival
+                .packet
+                .timestamp_us()
+                .map_or((1, 0), |timestamp| (0, timestamp)),
+            arrival.sequence,
+        )
+    };
+
+    key(a).cmp(&key(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Packet {
+        id: u8,
+        slot: Option<u64>,
+        position: Option<u64>,
+        timestamp_us: Option<u64>,
+    }
+
+    impl Placement for Packet {
+        fn slot(&self) -> Option<u64> {
+            self.slot
+        }
+
+        fn position(&self) -> Option<u64> {
+            self.position
+        }
+
+        fn timestamp_us(&self) -> Option<u64> {
+            self.timestamp_us
+        }
+    }
+
+    fn packet(
+        id: u8,
+        slot: Option<u64>,
+        position: Option<u64>,
+        timestamp_us: Option<u64>,
+    ) -> Packet {
+        Packet {
+            id,
+            slot,
+            position,
+            timestamp_us,
+        }
+    }
+
+    fn ids(blocks: Vec<Block<Packet>>) -> Vec<(Option<u64>, Vec<u8>)> {
+        blocks
+            .into_iter()
+            .map(|block| {
+                let ids = block.packets.iter().map(|packet| packet.id).collect();
+                (block.slot, ids)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn orders_by_position_then_timestamp_then_arrival() {
+        let mut reorderer = Reorderer::new(0);
+
+        for packet in [
+            packet(1, Some(10), None, None),
+            packet(2, Some(10), None, Some(50)),
+            packet(3, Some(10), Some(2), None),
+            packet(4, Some(10), None, Some(20)),
+            packet(5, Some(10), Some(0), Some(90)),
+            packet(6, Some(10), None, None),
+        ] {
+            assert!(reorderer.push(packet).is_empty());
+        }
+
+        assert_eq!(ids(reorderer.flush()), [(Some(10), vec![5, 3, 4, 2, 1, 6])]);
+    }
+
+    #[test]
+    fn holds_a_slot_for_lag_slots() {
+        let mut reorderer = Reorderer::new(2);
+
+        assert!(reorderer
+            .push(packet(1, Some(10), Some(1), None))
+            .is_empty());
+        assert!(reorderer
+            .push(packet(2, Some(11), Some(0), None))
+            .is_empty());
+        assert!(reorderer
+            .push(packet(3, Some(12), Some(0), None))
+            .is_empty());
+        // two slots on, slot 10 can still fill in
+        assert!(reorderer
+            .push(packet(4, Some(10), Some(0), None))
+            .is_empty());
+
+        assert_eq!(
+            ids(reorderer.push(packet(5, Some(14), Some(0), None))),
+            [(Some(10), vec![4, 1]), (Some(11), vec![2])]
+        );
+        assert_eq!(
+            ids(reorderer.flush()),
+            [(Some(12), vec![3]), (Some(14), vec![5])]
+        );
+        assert!(reorderer.flush().is_empty());
+    }
+
+    #[test]
+    fn passes_late_and_unslotted_packets_straight_through() {
+        let mut reorderer = Reorderer::new(0);
+
+        assert!(reorderer
+            .push(packet(1, Some(10), Some(0), None))
+            .is_empty());
+        assert_eq!(
+            ids(reorderer.push(packet(2, Some(11), Some(0), None))),
+            [(Some(10), vec![1])]
+        );
+
+        // slot 10 has been released
+        assert_eq!(
+            ids(reorderer.push(packet(3, Some(10), Some(1), None))),
+            [(Some(10), vec![3])]
+        );
+        assert_eq!(
+            ids(reorderer.push(packet(4, None, Some(0), None))),
+            [(None, vec![4])]
+        );
+
+        assert_eq!(ids(reorderer.flush()), [(Some(11), vec![2])]);
+    }
+}
@@ -0,0 +1,8 @@
+use std::fmt::Display;
+
+use serde::Serializer;
+
+/// Serializes a field through its `Display` impl, for keys and signatures.
+pub fn display<S: Serializer>(value: &impl Display, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
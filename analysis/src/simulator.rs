@@ -0,0 +1,60 @@
+use serde::Serialize;
+
+use crate::pool::Pool;
+
+#[derive(Debug, Serialize)]
+pub struct VictimEstimate {
+    /// Output the victim would have received had the front-run not happened.
+    pub fair_out: u64,
+    pub out: u64,
+    pub loss: u64,
+}
+
+/// What a sandwich does according to the constant product model.
+#[derive(Debug, Serialize)]
+pub struct SandwichEstimate {
+    pub victims: Vec<VictimEstimate>,
+    /// Back-run output minus front-run input, in the token the attacker
+    /// started with.
+    pub attacker_profit: i128,
+}
+
+impl SandwichEstimate {
+    pub fn victim_loss(&self) -> u64 {
+        self.victims.iter().map(|victim| victim.loss).sum()
+    }
+}
+
+/// Runs the front-run, each victim in order and the back-run through `pool`,
+/// which is oriented with the token the attacker and victims sell as input.
+pub fn simulate_sandwich(
+    pool: Pool,
+    front_amount_in: u64,
+    victim_amounts_in: &[u64],
+) -> SandwichEstimate {
+    let mut fair_pool = pool;
+    let mut pool = pool;
+
+    let front_amount_out = pool.swap(front_amount_in);
+
+    let victims = victim_amounts_in
+        .iter()
+        .map(|amount_in| {
+            let fair_out = fair_pool.swap(*amount_in);
+            let out = pool.swap(*amount_in);
+
+            VictimEstimate {
+                fair_out,
+                out,
+                loss: fair_out.saturating_sub(out),
+            }
+        })
+        .collect();
+
+    let back_amount_out = pool.reversed().amount_out(front_amount_out);
+
+    SandwichEstimate {
+        victims,
+        attacker_profit: i128::from(back_amount_out) - i128::from(front_amount_in),
+    }
+}
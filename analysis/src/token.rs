@@ -0,0 +1,114 @@
+use anyhow::Result;
+use spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    state::{Account, Mint},
+};
+
+use crate::pool::TransferFee;
+
+/// Balance of a token account of either token program.
+pub fn amount(data: &[u8]) -> Result<u64> {
+    Ok(StateWithExtensions::<Account>::unpack(data)?.base.amount)
+}
+
+/// Transfer fee a mint charges in `epoch`. Legacy mints and Token-2022 mints
+/// without the extension charge none.
+pub fn transfer_fee(mint_data: &[u8], epoch: u64) -> Result<TransferFee> {
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+
+    let Ok(config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(TransferFee::default());
+    };
+    let fee = config.get_epoch_fee(epoch);
+
+    Ok(TransferFee {
+        basis_points: fee.transfer_fee_basis_points.into(),
+        maximum_fee: fee.maximum_fee.into(),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use solana_sdk::program_pack::Pack;
+    use spl_token_2022::extension::{
+        transfer_fee::TransferFee as EpochFee, BaseStateWithExtensionsMut, ExtensionType,
+        StateWithExtensionsMut,
+    };
+
+    use super::*;
+
+    pub(crate) fn mint_with_transfer_fee(older: EpochFee, newer: EpochFee) -> Vec<u8> {
+        let len =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig])
+                .unwrap();
+        let mut data = vec![0; len];
+
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let config = mint.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.older_transfer_fee = older;
+        config.newer_transfer_fee = newer;
+        mint.base.decimals = 6;
+        mint.base.is_initialized = true;
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+
+        data
+    }
+
+    #[test]
+    fn reads_the_fee_for_the_epoch() {
+        let epoch_fee = |epoch: u64, basis_points: u16, maximum_fee: u64| EpochFee {
+            epoch: epoch.into(),
+            transfer_fee_basis_points: basis_points.into(),
+            maximum_fee: maximum_fee.into(),
+        };
+        let data = mint_with_transfer_fee(epoch_fee(0, 50, 1_000), epoch_fee(600, 100, 5_000));
+
+        assert_eq!(
+            transfer_fee(&data, 599).unwrap(),
+            TransferFee {
+                basis_points: 50,
+                maximum_fee: 1_000
+            }
+        );
+        assert_eq!(
+            transfer_fee(&data, 600).unwrap(),
+            TransferFee {
+                basis_points: 100,
+                maximum_fee: 5_000
+            }
+        );
+    }
+
+    #[test]
+    fn legacy_mint_has_no_fee() {
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            decimals: 9,
+            is_initialized: true,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+
+        assert_eq!(transfer_fee(&data, 0).unwrap(), TransferFee::default());
+    }
+
+    #[test]
+    fn fee_rounds_up_and_caps() {
+        let fee = TransferFee {
+            basis_points: 100,
+            maximum_fee: 50,
+        };
+
+        assert_eq!(fee.fee(0), 0);
+        assert_eq!(fee.fee(1), 1);
+        assert_eq!(fee.fee(250), 3);
+        assert_eq!(fee.fee(1_000_000), 50);
+        assert_eq!(fee.after_fee(250), 247);
+
+        assert_eq!(fee.before_fee(0), 0);
+        assert_eq!(fee.before_fee(247), 250);
+        assert_eq!(fee.after_fee(fee.before_fee(1_000_000)), 1_000_000);
+        assert_eq!(fee.before_fee(1_000_000), 1_000_050);
+    }
+}
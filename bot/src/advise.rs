@@ -0,0 +1,68 @@
+use anyhow::Result;
+use clap::Args;
+use sandy_analysis::{
+    advisor::{advise, exposure, Advice, Exposure},
+    pool::{Pool, TransferFee},
+};
+use serde::Serialize;
+
+#[derive(Args)]
+pub struct AdviseArgs {
+    /// Pool reserve of the token being sold
+    reserve_in: u64,
+    /// Pool reserve of the token being bought
+    reserve_out: u64,
+    amount_in: u64,
+
+    /// Report the exposure at this minimum instead of the advised one
+    #[arg(long)]
+    minimum_amount_out: Option<u64>,
+
+    /// Token-2022 transfer fee of the token being sold, in basis points
+    #[arg(long, default_value_t = 0)]
+    transfer_fee_in_bps: u16,
+    /// Cap on the transfer fee of the token being sold
+    #[arg(long, default_value_t = u64::MAX)]
+    maximum_fee_in: u64,
+
+    /// Token-2022 transfer fee of the token being bought, in basis points
+    #[arg(long, default_value_t = 0)]
+    transfer_fee_out_bps: u16,
+    /// Cap on the transfer fee of the token being bought
+    #[arg(long, default_value_t = u64::MAX)]
+    maximum_fee_out: u64,
+}
+
+#[derive(Serialize)]
+struct AdviceReport {
+    advice: Advice,
+    exposure: Option<Exposure>,
+}
+
+/// Prints the advice for a swap along with its exposure, at
+/// `--minimum-amount-out` if given and at the advised minimum otherwise.
+pub fn report(args: AdviseArgs) -> Result<()> {
+    let pool = Pool::raydium(args.reserve_in, args.reserve_out).with_transfer_fees(
+        TransferFee {
+            basis_points: args.transfer_fee_in_bps,
+            maximum_fee: args.maximum_fee_in,
+        },
+        TransferFee {
+            basis_points: args.transfer_fee_out_bps,
+            maximum_fee: args.maximum_fee_out,
+        },
+    );
+    let advice = advise(pool, args.amount_in);
+    let exposure = exposure(
+        pool,
+        args.amount_in,
+        args.minimum_amount_out.unwrap_or(advice.minimum_amount_out),
+    );
+
+    println!(
+        "{}",
+        serde_json::to_string(&AdviceReport { advice, exposure })?
+    );
+
+    Ok(())
+}
@@ -0,0 +1,338 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Seek, SeekFrom, Write},
+    path::Path,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use flate2::{bufread::GzDecoder, read::MultiGzDecoder, write::GzEncoder, Compression};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    signal,
+    sync::mpsc::{channel, Sender},
+};
+
+use crate::relayer::{forward_incoming_transactions, PendingTransaction};
+
+/// A relayer batch as it was received, one JSON line in the archive.
+#[derive(Serialize, Deserialize)]
+pub struct ArchivedBatch {
+    pub sequence: u64,
+    pub received_at_us: u64,
+    pub pending_transactions: PendingTransaction,
+}
+
+/// Appends batches to a gzip compressed JSON lines archive.
+///
+/// Each recording session adds a new gzip member to the end of the file, so
+/// an archive is never rewritten and sessions can be appended to it freely.
+pub struct Recorder {
+    encoder: GzEncoder<File>,
+    next_sequence: u64,
+}
+
+impl Recorder {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(path)?;
+
+        // carry the sequence on from whatever is already in the archive
+        let next_sequence = repair(&mut file)?;
+        file.seek(SeekFrom::End(0))?;
+
+        Ok(Self {
+            encoder: GzEncoder::new(file, Compression::default()),
+            next_sequence,
+        })
+    }
+
+    pub fn record(
+        &mut self,
+        pending_transactions: PendingTransaction,
+        received_at: SystemTime,
+    ) -> Result<()> {
+        let batch = ArchivedBatch {
+            sequence: self.next_sequence,
+            received_at_us: received_at.duration_since(UNIX_EPOCH)?.as_micros() as u64,
+            pending_transactions,
+        };
+
+        serde_json::to_writer(&mut self.encoder, &batch)?;
+        self.encoder.write_all(b"\n")?;
+        // sync flush so a crash loses at most the batch being written
+        self.encoder.flush()?;
+
+        self.next_sequence += 1;
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        self.encoder.finish()?;
+        Ok(())
+    }
+}
+
+// A session killed mid-write leaves a gzip member without a trailer, and
+// readers stop at it. What can still be read of it is rewritten as a
+// complete member so that sessions appended after it stay readable.
+fn repair(file: &mut File) -> Result<u64> {
+    let mut next_sequence = 0;
+    let mut cut_short_at = None;
+
+    let mut reader = BufReader::new(&*file);
+    while !reader.fill_buf()?.is_empty() {
+        let member_start = reader.stream_position()?;
+        let complete = read_member(&mut reader, |_, batch| next_sequence = batch.sequence + 1);
+        if !complete {
+            cut_short_at = Some(member_start);
+            break;
+        }
+    }
+
+    let Some(member_start) = cut_short_at else {
+        return Ok(next_sequence);
+    };
+
+    let mut lines = Vec::new();
+    let mut reader = BufReader::new(&*file);
+    reader.seek(SeekFrom::Start(member_start))?;
+    read_member(&mut reader, |line, _| lines.push(line));
+
+    warn!(
+        "Archive ends with a session cut short, recovered {} batches of it",
+        lines.len()
+    );
+
+    file.set_len(member_start)?;
+    file.seek(SeekFrom::End(0))?;
+
+    let mut encoder = GzEncoder::new(&*file, Compression::default());
+    for line in lines {
+        encoder.write_all(line.as_bytes())?;
+        encoder.write_all(b"\n")?;
+    }
+    encoder.finish()?;
+
+    Ok(next_sequence)
+}
+
+// Reads one gzip member, handing each batch to `each` along with its line,
+// and returns whether the member was complete.
+fn read_member(reader: &mut impl BufRead, mut each: impl FnMut(String, ArchivedBatch)) -> bool {
+    for line in BufReader::new(GzDecoder::new(reader)).lines() {
+        let Ok(line) = line else {
+            return false;
+        };
+        let Ok(batch) = serde_json::from_str(&line) else {
+            return false;
+        };
+        each(line, batch);
+    }
+
+    true
+}
+
+/// Reads batches back in the order they were recorded.
+pub struct ArchiveReader {
+    lines: std::io::Lines<BufReader<MultiGzDecoder<File>>>,
+}
+
+impl ArchiveReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let decoder = MultiGzDecoder::new(File::open(path)?);
+
+        Ok(Self {
+            lines: BufReader::new(decoder).lines(),
+        })
+    }
+}
+
+impl Iterator for ArchiveReader {
+    type Item = Result<ArchivedBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = match self.lines.next()? {
+            Ok(line) => line,
+            Err(e) => {
+                // the tail of a session that was killed mid-write
+                warn!("Archive ends with an unreadable record: {:?}", e);
+                return None;
+            }
+        };
+
+        Some(serde_json::from_str(&line).map_err(Into::into))
+    }
+}
+
+/// Streams an archive into `pending_transactions_tx` as if it came from the
+/// relayer, keeping the recorded gaps between batches divided by `speed`. A
+/// `speed` of zero replays as fast as the receiver allows.
+pub fn replay(path: &Path, speed: f64, pending_transactions_tx: Sender<PendingTransaction>) {
+    let reader = match ArchiveReader::open(path) {
+        Ok(reader) => reader,
+        Err(e) => {
+            error!("Failed to open archive: {:?}", e);
+            return;
+        }
+    };
+
+    let started_at = Instant::now();
+    let mut first_received_at_us = None;
+
+    for batch in reader {
+        let batch = match batch {
+            Ok(batch) => batch,
+            Err(e) => {
+                error!("Failed to read archived batch: {:?}", e);
+                return;
+            }
+        };
+
+        let first_received_at_us = *first_received_at_us.get_or_insert(batch.received_at_us);
+
+        if speed > 0.0 {
+            let offset = batch.received_at_us.saturating_sub(first_received_at_us);
+            let due = Duration::from_micros(offset).div_f64(speed);
+            if let Some(wait) = due.checked_sub(started_at.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+
+        if pending_transactions_tx
+            .blocking_send(batch.pending_transactions)
+            .is_err()
+        {
+            return;
+        }
+    }
+
+    info!("Finished replaying archive");
+}
+
+/// Records the relayer feed into the archive at `path` until interrupted.
+pub async fn record(path: &Path) -> Result<()> {
+    let mut recorder = Recorder::open(path)?;
+
+    let (pending_transactions_tx, mut pending_transactions_rx) = channel::<PendingTransaction>(100);
+
+    tokio::spawn(forward_incoming_transactions(pending_transactions_tx));
+
+    let shutdown = signal::ctrl_c();
+    tokio::pin!(shutdown);
+
+    loop {
+        let pending_transactions = tokio::select! {
+            biased;
+            _ = &mut shutdown => break,
+            pending_transactions = pending_transactions_rx.recv() => match pending_transactions {
+                Some(pending_transactions) => pending_transactions,
+                None => break,
+            },
+        };
+
+        recorder.record(pending_transactions, SystemTime::now())?;
+    }
+
+    info!("Archive holds {} batches", recorder.next_sequence);
+    recorder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::relayer::{Meta, Packet};
+
+    fn archive_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("sandy-{}-{name}.jsonl.gz", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn batch(id: u8) -> PendingTransaction {
+        PendingTransaction {
+            transactions: vec![Packet {
+                data: vec![id; 4],
+                meta: Some(Meta {
+                    size: 4,
+                    slot: Some(u64::from(id)),
+                    position: None,
+                    timestamp_us: None,
+                }),
+            }],
+        }
+    }
+
+    fn record_session(path: &Path, ids: &[u8]) {
+        let mut recorder = Recorder::open(path).unwrap();
+        for id in ids {
+            recorder.record(batch(*id), SystemTime::now()).unwrap();
+        }
+        recorder.finish().unwrap();
+    }
+
+    fn replayed(path: &Path) -> Vec<u8> {
+        let (tx, mut rx) = channel(16);
+        replay(path, 0.0, tx);
+
+        let mut ids = Vec::new();
+        while let Ok(pending_transactions) = rx.try_recv() {
+            ids.extend(
+                pending_transactions
+                    .transactions
+                    .iter()
+                    .map(|packet| packet.data[0]),
+            );
+        }
+        ids
+    }
+
+    fn sequences(path: &Path) -> Vec<u64> {
+        ArchiveReader::open(path)
+            .unwrap()
+            .map(|batch| batch.unwrap().sequence)
+            .collect()
+    }
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let path = archive_path("round-trip");
+
+        record_session(&path, &[1, 2]);
+        record_session(&path, &[3]);
+
+        assert_eq!(replayed(&path), [1, 2, 3]);
+        assert_eq!(sequences(&path), [0, 1, 2]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn appends_after_a_session_cut_short() {
+        let path = archive_path("cut-short");
+
+        record_session(&path, &[1]);
+        record_session(&path, &[2, 3]);
+
+        // lose the gzip trailer, as a killed session would
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(file.metadata().unwrap().len() - 8).unwrap();
+
+        record_session(&path, &[4]);
+
+        assert_eq!(replayed(&path), [1, 2, 3, 4]);
+        assert_eq!(sequences(&path), [0, 1, 2, 3]);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::Result;
+use log::warn;
+use sandy_analysis::benchmark::{benchmark, load_corpus, Score};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct ScoreReport {
+    #[serde(flatten)]
+    score: Score,
+    false_positives: usize,
+    false_negatives: usize,
+    precision: Option<f64>,
+    recall: Option<f64>,
+}
+
+impl From<Score> for ScoreReport {
+    fn from(score: Score) -> Self {
+        Self {
+            score,
+            false_positives: score.false_positives(),
+            false_negatives: score.false_negatives(),
+            precision: score.precision(),
+            recall: score.recall(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BenchReport {
+    fixtures: usize,
+    /// The rest are synthetic.
+    recorded_fixtures: usize,
+    overall: ScoreReport,
+    categories: BTreeMap<String, ScoreReport>,
+    misses: BTreeMap<String, ScoreReport>,
+}
+
+/// Runs the detector over the labelled corpus in `corpus` and prints its
+/// precision and recall, overall and by category, along with the fixtures
+/// it got wrong.
+pub fn report(corpus: &Path) -> Result<()> {
+    let corpus = load_corpus(corpus)?;
+    let benchmark = benchmark(&corpus);
+
+    if benchmark.recorded < corpus.len() {
+        warn!(
+            "{} of {} fixtures are synthetic, their scores don't measure real traffic",
+            corpus.len() - benchmark.recorded,
+            corpus.len()
+        );
+    }
+
+    let report = BenchReport {
+        fixtures: corpus.len(),
+        recorded_fixtures: benchmark.recorded,
+        overall: benchmark.overall.into(),
+        categories: benchmark
+            .categories
+            .into_iter()
+            .map(|(category, score)| (category, score.into()))
+            .collect(),
+        misses: benchmark
+            .misses
+            .into_iter()
+            .map(|(fixture, score)| (fixture, score.into()))
+            .collect(),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use std::path::Path;
+
+use anyhow::Result;
+use log::warn;
+use sandy_analysis::{
+    decoder::get_instruction_decoder,
+    detector::{detect, ObservedSwap},
+    reorder::{Block, Reorderer},
+};
+
+use crate::{
+    archive::ArchiveReader, confirm::SandwichReport, relayer::Packet,
+    utils::versioned_tx_from_packet,
+};
+
+/// Rebuilds the blocks of a recorded feed and prints every sandwich found
+/// within a slot as a JSON line.
+///
+/// Only feeds that carry slots can be split into blocks, packets without one
+/// are skipped.
+pub fn report(archive: &Path, lag: u64) -> Result<()> {
+    let mut reorderer = Reorderer::new(lag);
+    let mut unslotted = 0;
+
+    for batch in ArchiveReader::open(archive)? {
+        for packet in batch?.pending_transactions.transactions {
+            for block in reorderer.push(packet) {
+                report_block(block, &mut unslotted)?;
+            }
+        }
+    }
+
+    for block in reorderer.flush() {
+        report_block(block, &mut unslotted)?;
+    }
+
+    if unslotted > 0 {
+        warn!("Skipped {unslotted} packets without a slot");
+    }
+
+    Ok(())
+}
+
+fn report_block(block: Block<Packet>, unslotted: &mut usize) -> Result<()> {
+    if block.slot.is_none() {
+        *unslotted += block.packets.len();
+        return Ok(());
+    }
+
+    let swaps = observe(&block);
+
+    for sandwich in detect(&swaps) {
+        let mut report = SandwichReport::new(&swaps, &sandwich);
+        report.slot = block.slot;
+
+        println!("{}", serde_json::to_string(&report)?);
+    }
+
+    Ok(())
+}
+
+// pending transactions have no metadata, so nothing is settled and lookup
+// tables are left unresolved, which leaves swaps on pools in them undecoded
+fn observe(block: &Block<Packet>) -> Vec<ObservedSwap> {
+    let mut swaps = Vec::new();
+
+    for packet in &block.packets {
+        let Some(transaction) = versioned_tx_from_packet(packet) else {
+            continue;
+        };
+        let Some(&signature) = transaction.signatures.first() else {
+            continue;
+        };
+        let account_keys = transaction.message.static_account_keys();
+
+        for instruction in transaction.message.instructions() {
+            let Some(program_id) = account_keys.get(instruction.program_id_index as usize) else {
+                continue;
+            };
+            let Some(decoder) = get_instruction_decoder(program_id) else {
+                continue;
+            };
+
+            swaps.extend(
+                decoder
+                    .decode_swaps(&instruction.data, account_keys, &instruction.accounts)
+                    .into_iter()
+                    .map(|swap| ObservedSwap {
+                        signature,
+                        swap,
+                        settlement: None,
+                    }),
+            );
+        }
+    }
+
+    swaps
+}
+
+#[cfg(test)]
+mod tests {
+    use sandy_analysis::decoder::raydium_amm::RAYDIUM_AMM_PROGRAM_ID;
+    use solana_sdk::{
+        hash::Hash,
+        instruction::CompiledInstruction,
+        message::{v0, MessageHeader, VersionedMessage},
+        pubkey::Pubkey,
+        signature::Signature,
+        transaction::VersionedTransaction,
+    };
+
+    use super::*;
+    use crate::relayer::Meta;
+
+    fn packet(signatures: Vec<Signature>, pool_index: u8) -> Packet {
+        let mut data = vec![9];
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&900u64.to_le_bytes());
+
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                RAYDIUM_AMM_PROGRAM_ID,
+            ],
+            recent_blockhash: Hash::default(),
+            instructions: vec![CompiledInstruction::new_from_raw_parts(
+                2,
+                data,
+                vec![0, pool_index, 0],
+            )],
+            address_table_lookups: Vec::new(),
+        };
+
+        let transaction = VersionedTransaction {
+            signatures,
+            message: VersionedMessage::V0(message),
+        };
+        let data = bincode::serialize(&transaction).unwrap();
+
+        Packet {
+            meta: Some(Meta {
+                size: data.len() as u64,
+                slot: Some(1),
+                position: None,
+                timestamp_us: None,
+            }),
+            data,
+        }
+    }
+
+    #[test]
+    fn skips_what_it_cannot_decode() {
+        let block = Block {
+            slot: Some(1),
+            packets: vec![
+                // pool from a lookup table
+                packet(vec![Signature::new_unique()], 7),
+                packet(Vec::new(), 1),
+                packet(vec![Signature::new_unique()], 1),
+            ],
+        };
+
+        let swaps = observe(&block);
+
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].swap.amount_in, Some(1_000));
+    }
+}
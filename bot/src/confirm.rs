@@ -0,0 +1,73 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use sandy_analysis::{
+    detector::{confirm, detect, Confirmation, ObservedSwap, Sandwich},
+    metadata,
+    serde_helpers::display,
+};
+use serde::Serialize;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use crate::store::read_dumps;
+
+#[derive(Serialize)]
+pub struct SandwichReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    #[serde(serialize_with = "display")]
+    amm_id: Pubkey,
+    #[serde(serialize_with = "display")]
+    attacker: Pubkey,
+    #[serde(serialize_with = "display")]
+    front: Signature,
+    victims: Vec<String>,
+    #[serde(serialize_with = "display")]
+    back: Signature,
+    pub confirmation: Option<Confirmation>,
+}
+
+impl SandwichReport {
+    pub fn new(swaps: &[ObservedSwap], sandwich: &Sandwich) -> Self {
+        let front = &swaps[sandwich.front];
+
+        Self {
+            slot: None,
+            amm_id: front.swap.amm_id,
+            attacker: front.swap.user,
+            front: front.signature,
+            victims: sandwich
+                .victims
+                .iter()
+                .map(|victim| swaps[*victim].signature.to_string())
+                .collect(),
+            back: swaps[sandwich.back].signature,
+            confirmation: None,
+        }
+    }
+}
+
+/// Prints every sandwich found in the dump at `path` as a JSON line, along
+/// with its confirmation when the metadata was enough to settle it.
+///
+/// Swaps are settled net of the transfer fees of the mints dumped under
+/// `mints`, other mints are taken to charge none.
+pub fn report(path: &Path, tolerance: f64, mints: Option<&Path>) -> Result<()> {
+    let mints = match mints {
+        Some(mints) => read_dumps(mints)?
+            .into_iter()
+            .map(|(mint, account)| (mint, account.data))
+            .collect(),
+        None => HashMap::new(),
+    };
+    let swaps = metadata::load(path, &mints)?;
+
+    for sandwich in detect(&swaps) {
+        let mut report = SandwichReport::new(&swaps, &sandwich);
+        report.confirmation = confirm(&swaps, &sandwich, tolerance);
+
+        println!("{}", serde_json::to_string(&report)?);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use log::warn;
+use sandy_analysis::{
+    deviation::{deviations, Deviation, PoolPrice},
+    token,
+};
+use sandy_layout::RaydiumMarketV4;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::store::AccountStore;
+
+#[derive(Serialize)]
+struct DeviationReport<'a> {
+    snapshot: &'a str,
+    #[serde(flatten)]
+    deviation: Deviation,
+}
+
+/// Prints, as JSON lines, every pool whose price deviates from the other
+/// `pools` trading the same pair by more than `threshold_bps`, at each
+/// snapshot under `snapshots`.
+///
+/// Every subdirectory of `snapshots` is one snapshot, read with
+/// [`AccountStore::offline`] in the order of their names. It should hold the
+/// pools and their vaults.
+pub async fn report(snapshots: &Path, pools: &[Pubkey], threshold_bps: f64) -> Result<()> {
+    let mut snapshot_dirs = Vec::new();
+    for entry in fs::read_dir(snapshots)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            snapshot_dirs.push(entry.path());
+        }
+    }
+    snapshot_dirs.sort();
+
+    for snapshot_dir in snapshot_dirs {
+        let snapshot = snapshot_dir
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let account_store = AccountStore::offline(&snapshot_dir)?;
+
+        let mut prices = Vec::new();
+        for amm_id in pools {
+            match pool_price(&account_store, *amm_id).await {
+                Ok(Some(price)) => prices.push(price),
+                Ok(None) => {}
+                Err(e) => warn!("Skipping pool {} in snapshot {}: {:?}", amm_id, snapshot, e),
+            }
+        }
+
+        for deviation in deviations(&prices, threshold_bps) {
+            let report = DeviationReport {
+                snapshot: &snapshot,
+                deviation,
+            };
+            println!("{}", serde_json::to_string(&report)?);
+        }
+    }
+
+    Ok(())
+}
+
+async fn pool_price(account_store: &AccountStore, amm_id: Pubkey) -> Result<Option<PoolPrice>> {
+    let amm_market_data = account_store.get(&amm_id).await?;
+    let amm_market_account = RaydiumMarketV4::read(&amm_market_data.data)?;
+
+    // the vaults also hold the pool's pnl until it's taken, which doesn't
+    // trade
+    let base_reserve = token::amount(
+        &account_store
+            .get(&amm_market_account.base_vault)
+            .await?
+            .data,
+    )?
+    .saturating_sub(amm_market_account.base_need_take_pnl);
+    let quote_reserve = token::amount(
+        &account_store
+            .get(&amm_market_account.quote_vault)
+            .await?
+            .data,
+    )?
+    .saturating_sub(amm_market_account.quote_need_take_pnl);
+
+    Ok(PoolPrice::new(
+        amm_id,
+        (amm_market_account.base_mint, base_reserve),
+        (amm_market_account.quote_mint, quote_reserve),
+    ))
+}
@@ -0,0 +1,103 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, LineWriter, Write},
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use log::error;
+use sandy_analysis::{decoder::SwapInstruction, serde_helpers::display};
+use serde::{Serialize, Serializer};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodeOutcome {
+    Decoded,
+    NoDecoder,
+    NotSwap,
+    /// The transaction carries no signature, so nothing was decoded.
+    Unsigned,
+}
+
+/// One line of the event log, emitted for every instruction of every pending
+/// transaction we look at.
+#[derive(Serialize)]
+pub struct SwapEvent<'a> {
+    pub timestamp_ms: u128,
+    #[serde(serialize_with = "signature")]
+    pub signature: Option<Signature>,
+    #[serde(serialize_with = "display")]
+    pub program_id: Pubkey,
+    pub decoder: Option<&'static str>,
+    #[serde(flatten)]
+    pub swap: Option<&'a SwapInstruction>,
+    pub outcome: DecodeOutcome,
+}
+
+impl<'a> SwapEvent<'a> {
+    pub fn new(
+        signature: Option<Signature>,
+        program_id: Pubkey,
+        decoder: Option<&'static str>,
+        swap: Option<&'a SwapInstruction>,
+        outcome: DecodeOutcome,
+    ) -> Self {
+        Self {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis(),
+            signature,
+            program_id,
+            decoder,
+            swap,
+            outcome,
+        }
+    }
+}
+
+fn signature<S: Serializer>(
+    signature: &Option<Signature>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match signature {
+        Some(signature) => display(signature, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Writes events as JSON lines to stdout or an append-only file.
+pub struct EventLog {
+    sink: Mutex<Box<dyn Write + Send>>,
+}
+
+impl EventLog {
+    /// Opens the sink at `path`, where `-` means stdout.
+    pub fn open(path: &Path) -> Result<Self> {
+        let sink: Box<dyn Write + Send> = if path == Path::new("-") {
+            Box::new(io::stdout())
+        } else {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            Box::new(LineWriter::new(file))
+        };
+
+        Ok(Self {
+            sink: Mutex::new(sink),
+        })
+    }
+
+    pub fn emit(&self, event: &impl Serialize) {
+        let mut sink = self.sink.lock().unwrap();
+
+        let res = serde_json::to_writer(&mut *sink, event)
+            .map_err(io::Error::from)
+            .and_then(|_| sink.write_all(b"\n"));
+
+        if let Err(e) = res {
+            error!("Failed to write event: {:?}", e);
+        }
+    }
+}
@@ -1,147 +1,251 @@
-use std::sync::Arc;
-
-use decoder::get_instruction_decoder;
-use log::{error, info, warn};
+use std::{net::SocketAddr, num::NonZeroUsize, path::PathBuf, sync::Arc, time::Instant};
+
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand};
+use events::EventLog;
+use log::info;
+use metrics::Metrics;
+use pipeline::Pipeline;
 use relayer::{forward_incoming_transactions, PendingTransaction};
-use reqwest::Client;
-use serde_json::json;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{pubkey, pubkey::Pubkey, signature::Keypair, signer::EncodableKey};
-use tokio::sync::mpsc::channel;
-use transaction::TransactionBuilder;
-use utils::{get_pool_details, to_base_58, versioned_tx_from_packet};
-
-mod decoder;
+use store::AccountStore;
+use tokio::{
+    net::TcpListener,
+    signal,
+    sync::{mpsc::channel, Semaphore},
+    task::JoinSet,
+};
+
+mod advise;
+mod archive;
+mod bench;
+mod blocks;
+mod confirm;
+mod deviations;
+mod events;
+mod metrics;
+mod pipeline;
+mod pool_stats;
 mod relayer;
+mod store;
 mod transaction;
 mod utils;
 
 // replace with your deployed program id
 pub const SANDWICH_PROGRAM_ID: Pubkey = pubkey!("11111111111111111111111111111111");
 
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the bot against the relayer feed (default)
+    Run(RunArgs),
+    /// Report pool volume and fee revenue between two snapshots of a Raydium pool account
+    PoolStats { before: PathBuf, after: PathBuf },
+    /// Record the relayer feed into an archive that `run --replay` can play back
+    Record { archive: PathBuf },
+    /// Detect sandwiches in a dump of confirmed transactions and check them against their metadata
+    Confirm {
+        /// JSON array of `getTransaction` results with base64 encoding, in execution order
+        dump: PathBuf,
+
+        /// How far realised profit and loss may stray from the estimate, as a fraction of it
+        #[arg(long, default_value_t = 0.1)]
+        tolerance: f64,
+
+        /// Directory of `solana account --output json` dumps of Token-2022 mints with a transfer fee
+        #[arg(long)]
+        mints: Option<PathBuf>,
+    },
+    /// Advise the minimum output for a swap so that sandwiching it doesn't pay
+    Advise(advise::AdviseArgs),
+    /// Flag pools whose price deviates from other pools of the same pair, at each snapshot, for pairs of three pools or more
+    Deviations {
+        /// Directory with one subdirectory of `solana account --output json` dumps per snapshot
+        snapshots: PathBuf,
+
+        /// Raydium AMM v4 pools to compare
+        #[arg(long = "pool", required = true)]
+        pools: Vec<Pubkey>,
+
+        /// Smallest deviation from the pair's median price to flag, in basis points
+        #[arg(long, default_value_t = 100.0, value_parser = parse_threshold)]
+        threshold_bps: f64,
+    },
+    /// Rebuild the blocks of a recorded feed from packet slots and positions and detect sandwiches in each
+    Blocks {
+        archive: PathBuf,
+
+        /// Slots to wait for late packets before a slot is taken as complete
+        #[arg(long, default_value_t = 2)]
+        lag: u64,
+    },
+    /// Score the detector's precision and recall against a labelled fixture corpus
+    Bench {
+        /// Directory of labelled sequences, e.g. analysis/fixtures/detector
+        corpus: PathBuf,
+    },
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// Where to write the JSON lines event log, `-` for stdout
+    #[arg(long, default_value = "-")]
+    event_log: PathBuf,
+
+    /// Serve Prometheus metrics on this address, e.g. 127.0.0.1:9090
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Maximum number of packets processed at once
+    #[arg(long, default_value = "64")]
+    concurrency: NonZeroUsize,
+
+    /// Number of relayer batches buffered before the relayer is made to wait
+    #[arg(long, default_value = "100")]
+    channel_capacity: NonZeroUsize,
+
+    /// Read batches from an archive made by `record` instead of the relayer, without sending bundles
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    /// Replay speed as a multiple of the recorded pace, 0 replays as fast as possible
+    #[arg(long, default_value_t = 1.0, requires = "replay", value_parser = parse_speed)]
+    speed: f64,
+}
+
+fn parse_speed(speed: &str) -> Result<f64, String> {
+    let speed: f64 = speed.parse().map_err(|e| format!("{e}"))?;
+    if speed.is_finite() && speed >= 0.0 {
+        Ok(speed)
+    } else {
+        Err("must be a finite number of zero or more".to_string())
+    }
+}
+
+fn parse_threshold(threshold: &str) -> Result<f64, String> {
+    let threshold: f64 = threshold.parse().map_err(|e| format!("{e}"))?;
+    if threshold.is_finite() && threshold >= 0.0 {
+        Ok(threshold)
+    } else {
+        Err("must be a finite number of basis points, zero or more".to_string())
+    }
+}
+
 #[tokio::main]
-async fn main() {
+async fn main() -> Result<()> {
     env_logger::builder()
         .filter_level(log::LevelFilter::Info)
         .format_timestamp_nanos()
         .init();
 
-    //clear screen
-    print!("\x1B[2J\x1B[1;1H");
-
-    println!();
-
-    let rpc_client = Arc::new(RpcClient::new(
-        "https://api.mainnet-beta.solana.com".to_string(),
-    ));
-
-    let keypair = Arc::new(Keypair::read_from_file("../payer.json").unwrap());
-
-    let (pending_transactions_tx, mut pending_transactions_rx) = channel::<PendingTransaction>(100);
-
-    tokio::spawn(forward_incoming_transactions(pending_transactions_tx));
-
-    while let Some(pending_transactions) = pending_transactions_rx.recv().await {
-        for pending_transaction in pending_transactions.transactions {
-            let rpc_client = rpc_client.clone();
-            let keypair = keypair.clone();
-
-            tokio::spawn(async move {
-                let pending_transaction = match versioned_tx_from_packet(&pending_transaction) {
-                    Some(pending_transaction) => pending_transaction,
-                    None => {
-                        error!("Failed to deserialize transaction");
-                        return;
-                    }
-                };
-
-                info!(
-                    "Received pending transaction {:?}",
-                    pending_transaction.signatures
-                );
-
-                let account_keys = pending_transaction.message.static_account_keys();
-
-                for instruction in pending_transaction.message.instructions() {
-                    let program_id = account_keys[instruction.program_id_index as usize];
-
-                    let Some(decoder) = get_instruction_decoder(&program_id) else {
-                        warn!("No decoder found for program id: {:?}", program_id);
-                        continue;
-                    };
-
-                    let Some(user_swap_instruction) = decoder.decode_instruction(
-                        &instruction.data,
-                        pending_transaction.message.static_account_keys(),
-                        &instruction.accounts,
-                    ) else {
-                        warn!("No swap instruction found in transaction");
-                        continue;
-                    };
-
-                    info!("Found swap instruction: {:?}", user_swap_instruction);
-
-                    let pool_details =
-                        match get_pool_details(rpc_client.clone(), user_swap_instruction.amm_id)
-                            .await
-                        {
-                            Ok(pool_details) => pool_details,
-                            Err(e) => {
-                                error!("Failed to get pool details: {:?}", e);
-                                continue;
-                            }
-                        };
-
-                    info!(
-                        "Building bundle for token: {:?}",
-                        pool_details.token_address
-                    );
-
-                    let latest_blockhash = *pending_transaction.message.recent_blockhash();
-
-                    let transaction_builder = TransactionBuilder::new(
-                        user_swap_instruction.amount_in,
-                        user_swap_instruction.minimum_amount_out,
-                        keypair.clone(),
-                        pool_details,
-                        latest_blockhash,
-                    );
-
-                    let front_transaction = transaction_builder.front_transaction();
-                    let back_transaction = transaction_builder.back_transaction();
-
-                    let bundle = [front_transaction, pending_transaction, back_transaction]
-                        .iter()
-                        .map(to_base_58)
-                        .collect::<Vec<String>>();
-
-                    let res = Client::new()
-                        .post("https://ny.mainnet.block-engine.jito.wtf/api/v1/bundles")
-                        .json(&json!({"jsonrpc": "2.0", "id": 0, "method": "sendBundle", "params": [bundle]}))
-                        .send()
-                        .await;
-
-                    match res {
-                        Ok(res) => {
-                            let status = res.status();
-                            if status.is_success() {
-                                let body = res.json::<serde_json::Value>().await.unwrap();
-                                info!("Sent bundle with ID: {:?}", body.get("result"));
-                            } else {
-                                error!(
-                                    "Failed to send bundle: {:?}",
-                                    res.json::<serde_json::Value>().await
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            error!("Error when sending bundle: {:?}", e);
-                        }
-                    }
-
-                    break;
-                }
+    let cli = Cli::parse();
+
+    match cli.command.unwrap_or(Command::Run(cli.run)) {
+        Command::Run(args) => run(args).await,
+        Command::PoolStats { before, after } => pool_stats::report(&before, &after),
+        Command::Record { archive } => archive::record(&archive).await,
+        Command::Confirm {
+            dump,
+            tolerance,
+            mints,
+        } => confirm::report(&dump, tolerance, mints.as_deref()),
+        Command::Advise(args) => advise::report(args),
+        Command::Deviations {
+            snapshots,
+            pools,
+            threshold_bps,
+        } => deviations::report(&snapshots, &pools, threshold_bps).await,
+        Command::Blocks { archive, lag } => blocks::report(&archive, lag),
+        Command::Bench { corpus } => bench::report(&corpus),
+    }
+}
+
+async fn run(args: RunArgs) -> Result<()> {
+    let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+
+    // a replay only analyses, it never signs or sends anything
+    let keypair = match args.replay {
+        Some(_) => None,
+        None => Some(Arc::new(Keypair::read_from_file("../payer.json").unwrap())),
+    };
+
+    let metrics = Arc::new(Metrics::new()?);
+    if let Some(metrics_addr) = args.metrics_addr {
+        let listener = TcpListener::bind(metrics_addr).await?;
+        tokio::spawn(metrics::serve(listener, metrics.clone()));
+    }
+
+    let pipeline = Arc::new(Pipeline {
+        keypair,
+        account_store: AccountStore::new(rpc_client),
+        event_log: EventLog::open(&args.event_log)?,
+        metrics,
+    });
+
+    let (pending_transactions_tx, mut pending_transactions_rx) =
+        channel::<PendingTransaction>(args.channel_capacity.get());
+
+    match args.replay {
+        Some(archive) => {
+            tokio::task::spawn_blocking(move || {
+                archive::replay(&archive, args.speed, pending_transactions_tx)
             });
         }
+        None => {
+            tokio::spawn(forward_incoming_transactions(pending_transactions_tx));
+        }
     }
+
+    let workers = Arc::new(Semaphore::new(args.concurrency.get()));
+    let mut in_flight = JoinSet::new();
+
+    let shutdown = signal::ctrl_c();
+    tokio::pin!(shutdown);
+
+    loop {
+        let pending_transactions = tokio::select! {
+            biased;
+            _ = &mut shutdown => break,
+            pending_transactions = pending_transactions_rx.recv() => match pending_transactions {
+                Some(pending_transactions) => pending_transactions,
+                None => break,
+            },
+        };
+
+        let received_at = Instant::now();
+
+        for packet in pending_transactions.transactions {
+            pipeline.metrics.packets_received.inc();
+
+            // while every worker is busy we stop reading the channel, which
+            // makes the relayer wait once the channel is full
+            let permit = workers.clone().acquire_owned().await?;
+            let pipeline = pipeline.clone();
+
+            in_flight.spawn(async move {
+                pipeline.process_packet(packet, received_at).await;
+                drop(permit);
+            });
+        }
+
+        while in_flight.try_join_next().is_some() {}
+    }
+
+    info!(
+        "Shutting down, draining {} in-flight packets",
+        in_flight.len()
+    );
+    while in_flight.join_next().await.is_some() {}
+
+    Ok(())
 }
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::error;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+pub struct Metrics {
+    registry: Registry,
+
+    pub packets_received: IntCounter,
+    pub deserialize_failures: IntCounter,
+    pub decodes: IntCounterVec,
+    pub detections: IntCounter,
+    pub replay_detections: IntCounter,
+    pub analysis_latency: Histogram,
+    pub report_latency: Histogram,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("sandy".to_string()), None)?;
+
+        let packets_received = IntCounter::new(
+            "packets_received_total",
+            "Packets received from the relayer",
+        )?;
+        let deserialize_failures = IntCounter::new(
+            "deserialize_failures_total",
+            "Packets that could not be deserialized into a transaction",
+        )?;
+        let decodes = IntCounterVec::new(
+            Opts::new("decodes_total", "Swap instructions decoded"),
+            &["decoder"],
+        )?;
+        let detections = IntCounter::new(
+            "detections_total",
+            "Decoded swaps on a resolved pool that a bundle was built for",
+        )?;
+        let replay_detections = IntCounter::new(
+            "replay_detections_total",
+            "Decoded swaps with a known input found while replaying, where no pool is resolved and no bundle built",
+        )?;
+        let analysis_latency = Histogram::with_opts(
+            HistogramOpts::new(
+                "analysis_latency_seconds",
+                "Time from receiving a packet to finding the swap in it to act on",
+            )
+            .buckets(vec![
+                0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+            ]),
+        )?;
+        let report_latency = Histogram::with_opts(
+            HistogramOpts::new(
+                "report_latency_seconds",
+                "Time from receiving a packet to the outcome of its bundle submission",
+            )
+            .buckets(vec![0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+        )?;
+
+        registry.register(Box::new(packets_received.clone()))?;
+        registry.register(Box::new(deserialize_failures.clone()))?;
+        registry.register(Box::new(decodes.clone()))?;
+        registry.register(Box::new(detections.clone()))?;
+        registry.register(Box::new(replay_detections.clone()))?;
+        registry.register(Box::new(analysis_latency.clone()))?;
+        registry.register(Box::new(report_latency.clone()))?;
+
+        Ok(Self {
+            registry,
+            packets_received,
+            deserialize_failures,
+            decodes,
+            detections,
+            replay_detections,
+            analysis_latency,
+            report_latency,
+        })
+    }
+
+    pub fn render(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            error!("Failed to encode metrics: {:?}", e);
+        }
+        buffer
+    }
+}
+
+/// Answers every connection on `listener` with the current metrics in the
+/// Prometheus text format, whatever the request path.
+pub async fn serve(listener: TcpListener, metrics: Arc<Metrics>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                error!("Failed to accept metrics connection: {:?}", e);
+                continue;
+            }
+        };
+
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &metrics).await {
+                error!("Failed to serve metrics: {:?}", e);
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, metrics: &Metrics) -> Result<()> {
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request).await?;
+
+    let body = metrics.render();
+    let header = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
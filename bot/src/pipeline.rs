@@ -0,0 +1,194 @@
+use std::{sync::Arc, time::Instant};
+
+use log::{error, info};
+use reqwest::Client;
+use sandy_analysis::decoder::get_instruction_decoder;
+use serde_json::json;
+use solana_sdk::signature::Keypair;
+
+use crate::{
+    events::{DecodeOutcome, EventLog, SwapEvent},
+    metrics::Metrics,
+    relayer::Packet,
+    store::AccountStore,
+    transaction::TransactionBuilder,
+    utils::{get_pool_details, to_base_58, versioned_tx_from_packet},
+};
+
+/// State shared by every packet task.
+pub struct Pipeline {
+    /// `None` when replaying an archive, in which case swaps are decoded and
+    /// logged but no bundle is built or sent.
+    pub keypair: Option<Arc<Keypair>>,
+    pub account_store: AccountStore,
+    pub event_log: EventLog,
+    pub metrics: Arc<Metrics>,
+}
+
+impl Pipeline {
+    pub async fn process_packet(&self, packet: Packet, received_at: Instant) {
+        let pending_transaction = match versioned_tx_from_packet(&packet) {
+            Some(pending_transaction) => pending_transaction,
+            None => {
+                self.metrics.deserialize_failures.inc();
+                error!("Failed to deserialize transaction");
+                return;
+            }
+        };
+
+        let account_keys = pending_transaction.message.static_account_keys();
+        let program_ids = pending_transaction
+            .message
+            .instructions()
+            .iter()
+            .map(|instruction| account_keys.get(instruction.program_id_index as usize));
+
+        let Some(&signature) = pending_transaction.signatures.first() else {
+            for program_id in program_ids.flatten() {
+                self.event_log.emit(&SwapEvent::new(
+                    None,
+                    *program_id,
+                    None,
+                    None,
+                    DecodeOutcome::Unsigned,
+                ));
+            }
+            return;
+        };
+
+        for (instruction, program_id) in pending_transaction
+            .message
+            .instructions()
+            .iter()
+            .zip(program_ids)
+        {
+            let Some(&program_id) = program_id else {
+                continue;
+            };
+
+            let Some(decoder) = get_instruction_decoder(&program_id) else {
+                self.event_log.emit(&SwapEvent::new(
+                    Some(signature),
+                    program_id,
+                    None,
+                    None,
+                    DecodeOutcome::NoDecoder,
+                ));
+                continue;
+            };
+
+            let swaps = decoder.decode_swaps(
+                &instruction.data,
+                pending_transaction.message.static_account_keys(),
+                &instruction.accounts,
+            );
+
+            if swaps.is_empty() {
+                self.event_log.emit(&SwapEvent::new(
+                    Some(signature),
+                    program_id,
+                    Some(decoder.name()),
+                    None,
+                    DecodeOutcome::NotSwap,
+                ));
+                continue;
+            }
+
+            for swap in &swaps {
+                self.event_log.emit(&SwapEvent::new(
+                    Some(signature),
+                    program_id,
+                    Some(decoder.name()),
+                    Some(swap),
+                    DecodeOutcome::Decoded,
+                ));
+                self.metrics
+                    .decodes
+                    .with_label_values(&[decoder.name()])
+                    .inc();
+            }
+
+            // a front-run is sized against the user's input and minimum, route
+            // legs that don't carry both are left alone
+            let Some((amount_in, minimum_amount_out, amm_id)) = swaps
+                .iter()
+                .find_map(|swap| Some((swap.amount_in?, swap.minimum_amount_out?, swap.amm_id)))
+            else {
+                continue;
+            };
+
+            self.metrics
+                .analysis_latency
+                .observe(received_at.elapsed().as_secs_f64());
+
+            let Some(keypair) = &self.keypair else {
+                self.metrics.replay_detections.inc();
+                break;
+            };
+
+            let pool_details = match get_pool_details(&self.account_store, amm_id).await {
+                Ok(pool_details) => pool_details,
+                Err(e) => {
+                    error!("Failed to get pool details: {:?}", e);
+                    continue;
+                }
+            };
+
+            self.metrics.detections.inc();
+            info!(
+                "Building bundle for token: {:?}",
+                pool_details.token_address
+            );
+
+            let latest_blockhash = *pending_transaction.message.recent_blockhash();
+
+            let transaction_builder = TransactionBuilder::new(
+                amount_in,
+                minimum_amount_out,
+                keypair.clone(),
+                pool_details,
+                latest_blockhash,
+            );
+
+            let front_transaction = transaction_builder.front_transaction();
+            let back_transaction = transaction_builder.back_transaction();
+
+            let bundle = [front_transaction, pending_transaction, back_transaction]
+                .iter()
+                .map(to_base_58)
+                .collect::<Vec<String>>();
+
+            let res = Client::new()
+                .post("https://ny.mainnet.block-engine.jito.wtf/api/v1/bundles")
+                .json(
+                    &json!({"jsonrpc": "2.0", "id": 0, "method": "sendBundle", "params": [bundle]}),
+                )
+                .send()
+                .await;
+
+            match res {
+                Ok(res) => {
+                    let status = res.status();
+                    if status.is_success() {
+                        let body = res.json::<serde_json::Value>().await.unwrap();
+                        info!("Sent bundle with ID: {:?}", body.get("result"));
+                    } else {
+                        error!(
+                            "Failed to send bundle: {:?}",
+                            res.json::<serde_json::Value>().await
+                        );
+                    }
+                }
+                Err(e) => {
+                    error!("Error when sending bundle: {:?}", e);
+                }
+            }
+
+            self.metrics
+                .report_latency
+                .observe(received_at.elapsed().as_secs_f64());
+
+            break;
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use sandy_analysis::pool_stats::PoolStats;
+use sandy_layout::RaydiumMarketV4;
+
+pub fn read_snapshot(path: &Path) -> Result<RaydiumMarketV4> {
+    let data = fs::read(path)?;
+    Ok(RaydiumMarketV4::read(&data)?)
+}
+
+pub fn report(before: &Path, after: &Path) -> Result<()> {
+    let before = read_snapshot(before)?;
+    let after = read_snapshot(after)?;
+
+    let stats = PoolStats::between(&before, &after)?;
+
+    let base_decimals = decimals(after.base_decimal)?;
+    let quote_decimals = decimals(after.quote_decimal)?;
+
+    println!("base mint:  {}", after.base_mint);
+    println!("quote mint: {}", after.quote_mint);
+    println!();
+    println!(
+        "base -> quote: {} in, {} out, {} fees",
+        ui_amount(stats.base_in, base_decimals),
+        ui_amount(stats.quote_out, quote_decimals),
+        ui_amount(stats.base_to_quote_fee.into(), base_decimals),
+    );
+    println!(
+        "quote -> base: {} in, {} out, {} fees",
+        ui_amount(stats.quote_in, quote_decimals),
+        ui_amount(stats.base_out, base_decimals),
+        ui_amount(stats.quote_to_base_fee.into(), quote_decimals),
+    );
+    println!();
+    println!(
+        "base volume:  {}",
+        ui_amount(stats.base_volume(), base_decimals)
+    );
+    println!(
+        "quote volume: {}",
+        ui_amount(stats.quote_volume(), quote_decimals)
+    );
+    println!(
+        "protocol pnl: {} base, {} quote",
+        ui_amount(stats.base_total_pnl.into(), base_decimals),
+        ui_amount(stats.quote_total_pnl.into(), quote_decimals),
+    );
+
+    Ok(())
+}
+
+// past 38 decimals the scale no longer fits a u128
+fn decimals(decimals: u64) -> Result<u32> {
+    match u32::try_from(decimals) {
+        Ok(decimals) if decimals <= 38 => Ok(decimals),
+        _ => Err(anyhow!(
+            "snapshot has {decimals} decimals, is it a Raydium pool?"
+        )),
+    }
+}
+
+fn ui_amount(amount: u128, decimals: u32) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+
+    let scale = 10u128.pow(decimals);
+    format!(
+        "{}.{:0width$}",
+        amount / scale,
+        amount % scale,
+        width = decimals as usize
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_decimals_a_u128_cannot_scale() {
+        assert_eq!(decimals(38).unwrap(), 38);
+        assert!(decimals(39).is_err());
+        assert!(decimals(u64::MAX).is_err());
+        assert_eq!(ui_amount(1_500_000, 6), "1.500000");
+    }
+}
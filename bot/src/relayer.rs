@@ -1,4 +1,5 @@
 use futures_util::StreamExt;
+use sandy_analysis::reorder::Placement;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::Sender;
 use tokio_tungstenite::connect_async;
@@ -15,6 +16,29 @@ pub struct Packet {
 #[derive(Serialize, Deserialize)]
 pub struct Meta {
     pub size: u64,
+    /// Slot the transaction was executed in, or is expected to be.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    /// Index of the transaction within its slot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<u64>,
+    /// When the source saw the transaction, in microseconds since the epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_us: Option<u64>,
+}
+
+impl Placement for Packet {
+    fn slot(&self) -> Option<u64> {
+        self.meta.as_ref().and_then(|meta| meta.slot)
+    }
+
+    fn position(&self) -> Option<u64> {
+        self.meta.as_ref().and_then(|meta| meta.position)
+    }
+
+    fn timestamp_us(&self) -> Option<u64> {
+        self.meta.as_ref().and_then(|meta| meta.timestamp_us)
+    }
 }
 
 // for this example i just setup a simple javascript server to send transactions through ws
@@ -27,6 +51,13 @@ pub async fn forward_incoming_transactions(pending_transactions_tx: Sender<Pendi
         let msg = msg.into_text().unwrap();
         let pending_transaction: PendingTransaction = serde_json::from_str(&msg).unwrap();
 
-        pending_transactions_tx.send(pending_transaction).await.unwrap();
+        // the receiver is gone once the bot starts shutting down
+        if pending_transactions_tx
+            .send(pending_transaction)
+            .await
+            .is_err()
+        {
+            break;
+        }
     }
 }
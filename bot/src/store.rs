@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use solana_account_decoder::UiAccount;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use tokio::sync::OnceCell;
+
+type AccountCell = Arc<OnceCell<Arc<Account>>>;
+
+/// Accounts shared by every task, fetched over RPC the first time an
+/// account is asked for and served from memory afterwards.
+///
+/// Entries are never refreshed, so only read fields that don't change over
+/// the life of the account, like a pool's mints and vaults or a mint's
+/// token program.
+///
+/// An offline store holds a snapshot instead and never fetches.
+pub struct AccountStore {
+    rpc_client: Option<RpcClient>,
+    accounts: Mutex<HashMap<Pubkey, AccountCell>>,
+}
+
+// what `solana account <pubkey> --output json` writes
+#[derive(Deserialize)]
+struct AccountDump {
+    pubkey: String,
+    account: UiAccount,
+}
+
+impl AccountStore {
+    pub fn new(rpc_client: RpcClient) -> Self {
+        Self {
+            rpc_client: Some(rpc_client),
+            accounts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Loads a snapshot from a directory of `solana account --output json`
+    /// dumps, one account per `.json` file.
+    pub fn offline(snapshot: &Path) -> Result<Self> {
+        Ok(Self::with_accounts(read_dumps(snapshot)?))
+    }
+
+    /// An offline store holding just `accounts`.
+    pub fn with_accounts(accounts: HashMap<Pubkey, Account>) -> Self {
+        let accounts = accounts
+            .into_iter()
+            .map(|(pubkey, account)| {
+                (
+                    pubkey,
+                    Arc::new(OnceCell::new_with(Some(Arc::new(account)))),
+                )
+            })
+            .collect();
+
+        Self {
+            rpc_client: None,
+            accounts: Mutex::new(accounts),
+        }
+    }
+
+    pub async fn get(&self, pubkey: &Pubkey) -> Result<Arc<Account>> {
+        let cell = self
+            .accounts
+            .lock()
+            .unwrap()
+            .entry(*pubkey)
+            .or_default()
+            .clone();
+
+        // concurrent callers for the same account wait on a single fetch, a
+        // failed fetch leaves the cell empty so the next caller retries
+        let account = cell
+            .get_or_try_init(|| async {
+                let rpc_client = self
+                    .rpc_client
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("account {pubkey} is not in the snapshot"))?;
+                let account = rpc_client.get_account(pubkey).await?;
+                anyhow::Ok(Arc::new(account))
+            })
+            .await;
+
+        // keys come from transactions anyone can send, so don't keep a cell
+        // around for every one that failed
+        let account = match account {
+            Ok(account) => account,
+            Err(e) => {
+                let mut accounts = self.accounts.lock().unwrap();
+                if accounts
+                    .get(pubkey)
+                    .is_some_and(|held| Arc::ptr_eq(held, &cell) && !held.initialized())
+                {
+                    accounts.remove(pubkey);
+                }
+                return Err(e);
+            }
+        };
+
+        Ok(account.clone())
+    }
+}
+
+/// Reads every `solana account --output json` dump in `dir`, by address.
+pub fn read_dumps(dir: &Path) -> Result<HashMap<Pubkey, Account>> {
+    let mut accounts = HashMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+
+        let dump: AccountDump = serde_json::from_reader(BufReader::new(File::open(&path)?))?;
+        let account = dump
+            .account
+            .decode::<Account>()
+            .ok_or_else(|| anyhow!("{} does not hold binary account data", path.display()))?;
+
+        accounts.insert(dump.pubkey.parse()?, account);
+    }
+
+    Ok(accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn forgets_accounts_it_failed_to_get() {
+        let store = AccountStore::with_accounts(HashMap::new());
+
+        for _ in 0..3 {
+            assert!(store.get(&Pubkey::new_unique()).await.is_err());
+        }
+
+        assert!(store.accounts.lock().unwrap().is_empty());
+    }
+}
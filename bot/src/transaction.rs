@@ -41,9 +41,13 @@ impl TransactionBuilder {
         let user_source_token_account = get_associated_token_address(
             &keypair.pubkey(),
             &pubkey!("So11111111111111111111111111111111111111112"),
+            &pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+        );
+        let user_destination_token_account = get_associated_token_address(
+            &keypair.pubkey(),
+            &pool_details.token_address,
+            &pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
         );
-        let user_destination_token_account =
-            get_associated_token_address(&keypair.pubkey(), &pool_details.token_address);
 
         let sandwich_state =
             Pubkey::find_program_address(&[b"sandwich-state"], &SANDWICH_PROGRAM_ID).0;
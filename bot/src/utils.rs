@@ -1,8 +1,7 @@
-use crate::{decoder::raydium_amm::RAYDIUM_AMM_PROGRAM_ID, relayer::Packet};
-use anyhow::Result;
-use borsh::BorshDeserialize;
+use crate::{relayer::Packet, store::AccountStore};
+use anyhow::{bail, Result};
 use rand::seq::SliceRandom;
-use solana_client::nonblocking::rpc_client::RpcClient;
+use sandy_layout::RaydiumMarketV4;
 use solana_sdk::{
     packet::{Packet as SolanaPacket, PACKET_DATA_SIZE},
     pubkey,
@@ -10,7 +9,7 @@ use solana_sdk::{
     transaction::VersionedTransaction,
 };
 
-use std::{cmp::min, str::FromStr, sync::Arc};
+use std::{cmp::min, str::FromStr};
 
 pub fn versioned_tx_from_packet(p: &Packet) -> Option<VersionedTransaction> {
     let mut data = [0; PACKET_DATA_SIZE];
@@ -45,34 +44,9 @@ pub struct PoolDetails {
     pub pool_pc_token_account: Pubkey,
 }
 
-pub async fn get_pool_details(
-    rpc_client: Arc<RpcClient>,
-    amm_id: Pubkey,
-) -> Result<PoolDetails> {
-    let amm_market_data = rpc_client.get_account_data(&amm_id).await?;
-    let amm_market_account = RaydiumMarketV4::try_from_slice(&amm_market_data)?;
-
-    let serum_market = amm_market_account.market_id;
-
-    let pool_coin_token_account = Pubkey::find_program_address(
-        &[
-            &RAYDIUM_AMM_PROGRAM_ID.to_bytes(),
-            &serum_market.to_bytes(),
-            b"coin_vault_associated_seed",
-        ],
-        &RAYDIUM_AMM_PROGRAM_ID,
-    )
-    .0;
-
-    let pool_pc_token_account = Pubkey::find_program_address(
-        &[
-            &RAYDIUM_AMM_PROGRAM_ID.to_bytes(),
-            &serum_market.to_bytes(),
-            b"pc_vault_associated_seed",
-        ],
-        &RAYDIUM_AMM_PROGRAM_ID,
-    )
-    .0;
+pub async fn get_pool_details(account_store: &AccountStore, amm_id: Pubkey) -> Result<PoolDetails> {
+    let amm_market_data = account_store.get(&amm_id).await?;
+    let amm_market_account = RaydiumMarketV4::read(&amm_market_data.data)?;
 
     let reversed = amm_market_account.quote_mint.to_string()
         == "So11111111111111111111111111111111111111112";
@@ -83,11 +57,18 @@ pub async fn get_pool_details(
         amm_market_account.quote_mint
     };
 
+    // raydium amm v4 only moves legacy spl tokens, the sandwich program
+    // hands it that token program
+    let token_program = account_store.get(&token_address).await?.owner;
+    if token_program != pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA") {
+        bail!("{token_address} is owned by {token_program}, not the legacy token program");
+    }
+
     Ok(PoolDetails {
         token_address,
         amm_id,
-        pool_coin_token_account,
-        pool_pc_token_account,
+        pool_coin_token_account: amm_market_account.base_vault,
+        pool_pc_token_account: amm_market_account.quote_vault,
     })
 }
 
@@ -96,74 +77,69 @@ pub fn to_base_58(transaction: &VersionedTransaction) -> String {
     bs58::encode(serialized).into_string()
 }
 
-pub fn get_associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+pub fn get_associated_token_address(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
     Pubkey::find_program_address(
-        &[
-            owner.as_ref(),
-            &pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").to_bytes(),
-            mint.as_ref(),
-        ],
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
         &pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"),
     )
     .0
 }
 
-#[derive(BorshDeserialize)]
-struct RaydiumMarketV4 {
-    pub _status: u64,
-    pub _nonce: u64,
-    pub _max_order: u64,
-    pub _depth: u64,
-    pub _base_decimal: u64,
-    pub _quote_decimal: u64,
-    pub _state: u64,
-    pub _reset_flag: u64,
-    pub _min_size: u64,
-    pub _vol_max_cut_ratio: u64,
-    pub _amount_wave_ratio: u64,
-    pub _base_lot_size: u64,
-    pub _quote_lot_size: u64,
-    pub _min_price_multiplier: u64,
-    pub _max_price_multiplier: u64,
-    pub _system_decimal_value: u64,
-    pub _min_separate_numerator: u64,
-    pub _min_separate_denominator: u64,
-    pub _trade_fee_numerator: u64,
-    pub _trade_fee_denominator: u64,
-    pub _pnl_numerator: u64,
-    pub _pnl_denominator: u64,
-    pub _swap_fee_numerator: u64,
-    pub _swap_fee_denominator: u64,
-    pub _base_need_take_pnl: u64,
-    pub _quote_need_take_pnl: u64,
-    pub _quote_total_pnl: u64,
-    pub _base_total_pnl: u64,
-    pub _pool_open_time: u64,
-    pub _punish_pc_amount: u64,
-    pub _punish_coin_amount: u64,
-    pub _orderbook_to_init_time: u64,
-    pub _swap_base_in_amount: u128,
-    pub _swap_quote_out_amount: u128,
-    pub _swap_base2_quote_fee: u64,
-    pub _swap_quote_in_amount: u128,
-    pub _swap_base_out_amount: u128,
-    pub _swap_quote2_base_fee: u64,
-    // AMM vault
-    pub _base_vault: Pubkey,
-    pub _quote_vault: Pubkey,
-    // Mint
-    pub base_mint: Pubkey,
-    pub quote_mint: Pubkey,
-    pub _lp_mint: Pubkey,
-    // Market
-    pub _open_orders: Pubkey,
-    pub market_id: Pubkey,
-    pub _market_program_id: Pubkey,
-    pub _target_orders: Pubkey,
-    pub _withdraw_queue: Pubkey,
-    pub _lp_vault: Pubkey,
-    pub _owner: Pubkey,
-    // True circulating supply without lock up
-    pub _lp_reserve: u64,
-    pub _padding: [u64; 3],
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use solana_sdk::account::Account;
+
+    use super::*;
+
+    const WSOL: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+
+    async fn pool_details_for_mint_of(token_program: Pubkey) -> Result<PoolDetails> {
+        let (amm_id, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let market = RaydiumMarketV4 {
+            base_mint: mint,
+            quote_mint: WSOL,
+            ..RaydiumMarketV4::read(&[0; RaydiumMarketV4::LEN]).unwrap()
+        };
+
+        let account_store = AccountStore::with_accounts(HashMap::from([
+            (
+                amm_id,
+                Account {
+                    data: bytemuck::bytes_of(&market).to_vec(),
+                    ..Account::default()
+                },
+            ),
+            (
+                mint,
+                Account {
+                    owner: token_program,
+                    ..Account::default()
+                },
+            ),
+        ]));
+
+        get_pool_details(&account_store, amm_id).await
+    }
+
+    #[tokio::test]
+    async fn only_pools_of_legacy_tokens_are_sandwiched() {
+        let pool_details =
+            pool_details_for_mint_of(pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"))
+                .await
+                .unwrap();
+        assert_ne!(pool_details.token_address, WSOL);
+
+        // raydium amm v4 takes only the legacy token program for its swaps
+        assert!(
+            pool_details_for_mint_of(pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"))
+                .await
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,38 @@
+use std::fmt;
+
+use solana_program::program_error::ProgramError;
+
+pub mod math;
+mod pod;
+mod raydium;
+
+pub use pod::PodU128;
+pub use raydium::RaydiumMarketV4;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    InvalidLength { expected: usize, actual: usize },
+    Misaligned,
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength { expected, actual } => {
+                write!(
+                    f,
+                    "invalid account length: expected {expected}, got {actual}"
+                )
+            }
+            Self::Misaligned => write!(f, "account data is not aligned for zero-copy access"),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+impl From<LayoutError> for ProgramError {
+    fn from(_: LayoutError) -> Self {
+        ProgramError::InvalidAccountData
+    }
+}
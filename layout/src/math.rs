@@ -0,0 +1,250 @@
+//! Constant product math shared by the on-chain program and the off-chain
+//! analysis. The swap math is integer only, [`estimate_max_front_run`] uses
+//! `f64` and its `sqrt` from std, which on SBF run in software.
+
+/// Output of a constant product pool for `amount_in`, after the swap fee. A
+/// fee of 100% or more, or over a zero denominator, leaves nothing.
+pub fn amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> u64 {
+    let Some(fee_kept) = fee_denominator.checked_sub(fee_numerator) else {
+        return 0;
+    };
+    if fee_denominator == 0 {
+        return 0;
+    }
+
+    // k = y * x
+    let amount_in_after_fee =
+        u128::from(amount_in) * u128::from(fee_kept) / u128::from(fee_denominator);
+
+    let numerator = amount_in_after_fee * u128::from(reserve_out);
+    let denominator = u128::from(reserve_in) + amount_in_after_fee;
+    if denominator == 0 {
+        return 0;
+    }
+
+    (numerator / denominator) as u64
+}
+
+/// Largest `x` in `lb..=ub` for which `filled(x)` holds, where `filled` holds
+/// up to some boundary and not past it, or `None` if it doesn't hold at `lb`.
+///
+/// `guess` is where to start looking, usually [`estimate_max_front_run`].
+/// Walking out from it to a bracket and bisecting the bracket each halve or
+/// double a u64, so neither runs for more than 64 steps.
+pub fn largest_filled(lb: u64, ub: u64, guess: u64, filled: impl Fn(u64) -> bool) -> Option<u64> {
+    if !filled(lb) {
+        return None;
+    }
+    if filled(ub) {
+        return Some(ub);
+    }
+
+    let guess = guess.clamp(lb, ub);
+
+    // filled(lb) holds and filled(ub) doesn't, walk out from the guess until
+    // the boundary is between the two
+    let (mut lb, mut ub) = (lb, ub);
+    let mut step = 1u64;
+    if filled(guess) {
+        lb = guess;
+        while lb < ub {
+            let next = lb.saturating_add(step).min(ub);
+            if !filled(next) {
+                ub = next;
+                break;
+            }
+            lb = next;
+            step = step.saturating_mul(2);
+        }
+    } else {
+        ub = guess;
+        while lb < ub {
+            let next = ub.saturating_sub(step).max(lb);
+            if filled(next) {
+                lb = next;
+                break;
+            }
+            ub = next;
+            step = step.saturating_mul(2);
+        }
+    }
+
+    while ub - lb > 1 {
+        let mid = lb + (ub - lb) / 2;
+        if filled(mid) {
+            lb = mid;
+        } else {
+            ub = mid;
+        }
+    }
+
+    Some(lb)
+}
+
+/// Closed-form guess at the largest front-run after which a swap of
+/// `amount_in` still gets `minimum_amount_out`, off by rounding at most.
+pub fn estimate_max_front_run(
+    amount_in: u64,
+    minimum_amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> u64 {
+    let Some(fee_kept) = fee_denominator.checked_sub(fee_numerator) else {
+        return u64::MAX;
+    };
+    if minimum_amount_out == 0 || fee_denominator == 0 {
+        return u64::MAX;
+    }
+
+    // with g the fee multiplier, a front-run of x leaves the swap
+    // g * u * a * b / ((a + g * x) * (a + x + g * u)), solved for x at the
+    // minimum
+    let g = fee_kept as f64 / fee_denominator as f64;
+    let u = amount_in as f64;
+    let a = reserve_in as f64;
+    let b = reserve_out as f64;
+    let m = minimum_amount_out as f64;
+
+    // g * x^2 + q * x + c <= 0
+    let q = a + g * a + g * g * u;
+    let c = a * (a + g * u) - g * u * a * b / m;
+    if c >= 0.0 {
+        return 0;
+    }
+
+    // the smaller root is negative, this form of the larger one avoids
+    // cancelling q against the square root
+    let x = -2.0 * c / (q + (q * q - 4.0 * g * c).sqrt());
+
+    // saturating float to int cast
+    x as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // past this the swaps below get nothing out of these pools
+    const LIMIT: u64 = 5_000;
+
+    fn victim_out(front_amount_in: u64, amount_in: u64, reserve_in: u64, reserve_out: u64) -> u64 {
+        let front_amount_out = amount_out(front_amount_in, reserve_in, reserve_out, 25, 10_000);
+        amount_out(
+            amount_in,
+            reserve_in + front_amount_in,
+            reserve_out - front_amount_out,
+            25,
+            10_000,
+        )
+    }
+
+    fn max_front_run(
+        amount_in: u64,
+        minimum_amount_out: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+    ) -> Option<u64> {
+        let guess = estimate_max_front_run(
+            amount_in,
+            minimum_amount_out,
+            reserve_in,
+            reserve_out,
+            25,
+            10_000,
+        );
+        largest_filled(0, LIMIT, guess, |front_amount_in| {
+            victim_out(front_amount_in, amount_in, reserve_in, reserve_out) >= minimum_amount_out
+        })
+    }
+
+    #[test]
+    fn matches_brute_force_on_small_reserves() {
+        for (reserve_in, reserve_out) in [(1_000, 1_000), (1_000, 7_919), (5_003, 211), (97, 3_001)]
+        {
+            for amount_in in [1, 10, 57, 300] {
+                let outs: Vec<u64> = (0..=LIMIT)
+                    .map(|front_amount_in| {
+                        victim_out(front_amount_in, amount_in, reserve_in, reserve_out)
+                    })
+                    .collect();
+
+                for minimum_amount_out in 0..=outs[0] + 1 {
+                    let expected = outs
+                        .iter()
+                        .rposition(|out| *out >= minimum_amount_out)
+                        .map(|front_amount_in| front_amount_in as u64);
+
+                    assert_eq!(
+                        max_front_run(amount_in, minimum_amount_out, reserve_in, reserve_out),
+                        expected,
+                        "reserves {reserve_in}/{reserve_out}, in {amount_in}, \
+                         minimum out {minimum_amount_out}",
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fees_of_all_the_input_leave_nothing() {
+        assert_eq!(amount_out(1_000, 1_000, 1_000, 10_000, 10_000), 0);
+        assert_eq!(amount_out(1_000, 1_000, 1_000, 10_001, 10_000), 0);
+        assert_eq!(amount_out(1_000, 1_000, 1_000, 0, 0), 0);
+
+        assert_eq!(
+            estimate_max_front_run(10, 5, 1_000, 1_000, 10_001, 10_000),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn holds_everywhere_or_nowhere() {
+        assert_eq!(largest_filled(5, 100, 50, |_| true), Some(100));
+        assert_eq!(largest_filled(5, 100, 50, |_| false), None);
+        assert_eq!(
+            largest_filled(0, u64::MAX, 0, |x| x < u64::MAX),
+            Some(u64::MAX - 1)
+        );
+    }
+
+    #[test]
+    fn a_bad_guess_only_costs_steps() {
+        for guess in [0, 1, 999, 1_000, 1_001, u64::MAX] {
+            assert_eq!(
+                largest_filled(0, u64::MAX, guess, |x| x <= 1_000),
+                Some(1_000)
+            );
+        }
+    }
+
+    #[test]
+    fn large_reserves_stay_exact() {
+        let (reserve_in, reserve_out) = (48_210_773_019_334, 7_305_118_402_991_006);
+        let amount_in = 12_500_000_000;
+        let minimum_amount_out = 1_850_000_000_000;
+
+        let guess = estimate_max_front_run(
+            amount_in,
+            minimum_amount_out,
+            reserve_in,
+            reserve_out,
+            25,
+            10_000,
+        );
+        let filled = |front_amount_in| {
+            victim_out(front_amount_in, amount_in, reserve_in, reserve_out) >= minimum_amount_out
+        };
+        let front_amount_in = largest_filled(0, u64::MAX / 4, guess, filled).unwrap();
+
+        assert!(filled(front_amount_in));
+        assert!(!filled(front_amount_in + 1));
+    }
+}
@@ -0,0 +1,35 @@
+use std::fmt;
+
+use bytemuck::{Pod, Zeroable};
+
+/// A little-endian `u128` stored as raw bytes.
+///
+/// Raydium packs its `u128` counters at 8-byte boundaries, so a native `u128`
+/// field would make the struct larger than the on-chain account.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+#[repr(transparent)]
+pub struct PodU128([u8; 16]);
+
+impl PodU128 {
+    pub fn get(self) -> u128 {
+        u128::from_le_bytes(self.0)
+    }
+}
+
+impl From<u128> for PodU128 {
+    fn from(value: u128) -> Self {
+        Self(value.to_le_bytes())
+    }
+}
+
+impl From<PodU128> for u128 {
+    fn from(value: PodU128) -> Self {
+        value.get()
+    }
+}
+
+impl fmt::Debug for PodU128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
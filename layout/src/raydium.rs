@@ -0,0 +1,97 @@
+use bytemuck::{Pod, Zeroable};
+use solana_program::pubkey::Pubkey;
+
+use crate::{LayoutError, PodU128};
+
+/// Raydium AMM v4 pool account (`AmmInfo`), 752 bytes.
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct RaydiumMarketV4 {
+    pub status: u64,
+    pub nonce: u64,
+    pub max_order: u64,
+    pub depth: u64,
+    pub base_decimal: u64,
+    pub quote_decimal: u64,
+    pub state: u64,
+    pub reset_flag: u64,
+    pub min_size: u64,
+    pub vol_max_cut_ratio: u64,
+    pub amount_wave_ratio: u64,
+    pub base_lot_size: u64,
+    pub quote_lot_size: u64,
+    pub min_price_multiplier: u64,
+    pub max_price_multiplier: u64,
+    pub system_decimal_value: u64,
+    // Fees
+    pub min_separate_numerator: u64,
+    pub min_separate_denominator: u64,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub pnl_numerator: u64,
+    pub pnl_denominator: u64,
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+    // Cumulative state
+    pub base_need_take_pnl: u64,
+    pub quote_need_take_pnl: u64,
+    pub quote_total_pnl: u64,
+    pub base_total_pnl: u64,
+    pub pool_open_time: u64,
+    pub punish_pc_amount: u64,
+    pub punish_coin_amount: u64,
+    pub orderbook_to_init_time: u64,
+    pub swap_base_in_amount: PodU128,
+    pub swap_quote_out_amount: PodU128,
+    pub swap_base2_quote_fee: u64,
+    pub swap_quote_in_amount: PodU128,
+    pub swap_base_out_amount: PodU128,
+    pub swap_quote2_base_fee: u64,
+    // AMM vault
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    // Mint
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    // Market
+    pub open_orders: Pubkey,
+    pub market_id: Pubkey,
+    pub market_program_id: Pubkey,
+    pub target_orders: Pubkey,
+    pub withdraw_queue: Pubkey,
+    pub lp_vault: Pubkey,
+    pub owner: Pubkey,
+    // True circulating supply without lock up
+    pub lp_reserve: u64,
+    pub padding: [u64; 3],
+}
+
+const _: () = assert!(std::mem::size_of::<RaydiumMarketV4>() == RaydiumMarketV4::LEN);
+
+impl RaydiumMarketV4 {
+    pub const LEN: usize = 752;
+
+    /// Borrows the pool straight out of account data without copying.
+    pub fn load(data: &[u8]) -> Result<&Self, LayoutError> {
+        check_len(data)?;
+        bytemuck::try_from_bytes(data).map_err(|_| LayoutError::Misaligned)
+    }
+
+    /// Copies the pool out of account data with any alignment, e.g. bytes
+    /// returned over RPC.
+    pub fn read(data: &[u8]) -> Result<Self, LayoutError> {
+        check_len(data)?;
+        Ok(bytemuck::pod_read_unaligned(data))
+    }
+}
+
+fn check_len(data: &[u8]) -> Result<(), LayoutError> {
+    if data.len() != RaydiumMarketV4::LEN {
+        return Err(LayoutError::InvalidLength {
+            expected: RaydiumMarketV4::LEN,
+            actual: data.len(),
+        });
+    }
+    Ok(())
+}
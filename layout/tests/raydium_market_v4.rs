@@ -0,0 +1,125 @@
+use std::{fs, path::PathBuf};
+
+use sandy_layout::{LayoutError, RaydiumMarketV4};
+use solana_program::{pubkey, pubkey::Pubkey};
+
+// Image of the SOL-USDC pool account, 58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2.
+// Its addresses are the pool's on mainnet but its counters and pnl are
+// filled in by hand, no dump has been taken yet. To swap in the real
+// account run
+//
+//   solana account 58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2 \
+//       --output-file layout/tests/fixtures/sol_usdc_amm_v4.bin
+//   UPDATE_GOLDEN=1 cargo test -p sandy-layout
+//
+// the other tests only check what holds for the real account too.
+const FIXTURE: &str = "sol_usdc_amm_v4";
+
+fn fixture_path(extension: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(FIXTURE)
+        .with_extension(extension)
+}
+
+fn fixture() -> Vec<u8> {
+    fs::read(fixture_path("bin")).unwrap()
+}
+
+#[test]
+fn matches_golden_file() {
+    let data = fixture();
+    let rendered = format!("{:#?}\n", RaydiumMarketV4::read(&data).unwrap());
+
+    let golden_path = fixture_path("golden");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden_path, &rendered).unwrap();
+    }
+
+    assert_eq!(rendered, fs::read_to_string(golden_path).unwrap());
+}
+
+#[test]
+fn load_and_read_agree() {
+    let data = fixture();
+
+    // copy into u64 storage so the zero-copy path sees aligned bytes
+    let mut aligned = vec![0u64; RaydiumMarketV4::LEN / 8];
+    bytemuck::cast_slice_mut::<u64, u8>(&mut aligned).copy_from_slice(&data);
+
+    let loaded = RaydiumMarketV4::load(bytemuck::cast_slice(&aligned)).unwrap();
+    let read = RaydiumMarketV4::read(&data).unwrap();
+
+    assert_eq!(bytemuck::bytes_of(loaded), bytemuck::bytes_of(&read));
+}
+
+#[test]
+fn fields_sit_at_on_chain_offsets() {
+    let data = fixture();
+    let market = RaydiumMarketV4::read(&data).unwrap();
+
+    assert_eq!(market.quote_mint.as_ref(), &data[432..464]);
+    assert_eq!(market.base_vault.as_ref(), &data[336..368]);
+    assert_eq!(market.market_id.as_ref(), &data[528..560]);
+    assert_eq!(
+        market.base_mint,
+        pubkey!("So11111111111111111111111111111111111111112")
+    );
+    assert_eq!(
+        market.quote_mint,
+        pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")
+    );
+    assert_eq!(market.swap_fee_numerator, 25);
+    assert_eq!(market.swap_fee_denominator, 10_000);
+    assert_eq!(
+        market.swap_base_in_amount.get(),
+        u128::from_le_bytes(data[256..272].try_into().unwrap())
+    );
+}
+
+#[test]
+fn addresses_are_the_mainnet_pools() {
+    let data = fixture();
+    let key_at = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]).unwrap();
+
+    // read straight from the bytes, the parser's offsets aside
+    for (offset, key) in [
+        (336, pubkey!("DQyrAcCrDXQ7NeoqGgDCZwBvWDcYmFCjSb9JtteuvPpz")),
+        (368, pubkey!("HLmqeL62xR1QoZ1HKKbXRrdN1p3phKpxRMb2VVopvBBz")),
+        (400, pubkey!("So11111111111111111111111111111111111111112")),
+        (432, pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")),
+        (528, pubkey!("8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6")),
+        (560, pubkey!("srmqPvymJeFKQ4zGQed1GFppgkRHL2kCcaAXQNT7GsM")),
+    ] {
+        assert_eq!(key_at(offset), key, "offset {offset}");
+    }
+}
+
+#[test]
+fn rejects_wrong_length() {
+    let data = fixture();
+
+    assert_eq!(
+        RaydiumMarketV4::read(&data[..RaydiumMarketV4::LEN - 1]).unwrap_err(),
+        LayoutError::InvalidLength {
+            expected: 752,
+            actual: 751
+        }
+    );
+
+    let mut longer = data.clone();
+    longer.push(0);
+    assert!(RaydiumMarketV4::read(&longer).is_err());
+}
+
+#[test]
+fn load_rejects_misaligned_data() {
+    let mut storage = vec![0u64; RaydiumMarketV4::LEN / 8 + 1];
+    let bytes = bytemuck::cast_slice_mut::<u64, u8>(&mut storage);
+    bytes[1..=RaydiumMarketV4::LEN].copy_from_slice(&fixture());
+
+    assert_eq!(
+        RaydiumMarketV4::load(&bytes[1..=RaydiumMarketV4::LEN]).unwrap_err(),
+        LayoutError::Misaligned
+    );
+}
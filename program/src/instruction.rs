@@ -9,10 +9,20 @@ pub struct SwapIn {
     pub user_minimum_amount_out: u64,
 }
 
+/// Fails the transaction if the pool would now give less than
+/// `quoted_amount_out` minus `tolerance_bps` for `amount_in`.
+#[derive(BorshDeserialize)]
+pub struct Guard {
+    pub amount_in: u64,
+    pub quoted_amount_out: u64,
+    pub tolerance_bps: u16,
+}
+
 pub enum SandyInstruction {
     Initialize(SandwichState),
     SwapIn(SwapIn),
     SwapOut,
+    Guard(Guard),
 }
 
 impl SandyInstruction {
@@ -25,6 +35,7 @@ impl SandyInstruction {
             0 => Self::Initialize(SandwichState::try_from_slice(rest)?),
             1 => Self::SwapIn(SwapIn::try_from_slice(rest)?),
             2 => Self::SwapOut,
+            3 => Self::Guard(Guard::try_from_slice(rest)?),
             _ => return Err(ProgramError::InvalidInstructionData),
         })
     }
@@ -12,7 +12,7 @@ entrypoint!(process_instruction);
 // replace with your program id
 solana_program::declare_id!("11111111111111111111111111111111");
 
-fn process_instruction(
+pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
@@ -1,21 +1,23 @@
+use sandy_layout::math::{amount_out, estimate_max_front_run, largest_filled};
+
+// raydium amm v4 charges 25 bps on every swap
+const FEE_NUMERATOR: u64 = 25;
+const FEE_DENOMINATOR: u64 = 10_000;
+
 pub struct AmountOut {
     pub amount_out: u64,
     pub new_reserve_a: u64,
     pub new_reserve_b: u64,
 }
 
-// k = y * x
-fn get_amount_out(amount_in: u64, reserve_a: u64, reserve_b: u64) -> AmountOut {
-    let amount_in_128 = u128::from(amount_in);
-    let reserve_a_128 = u128::from(reserve_a);
-    let reserve_b_128 = u128::from(reserve_b);
-
-    let amount_in_after_fee = amount_in_128 * (10000 - 25) / 10000;
-
-    let numerator = amount_in_after_fee * reserve_b_128;
-    let denominator = reserve_a_128 + amount_in_after_fee;
-
-    let amount_out = (numerator / denominator) as u64;
+pub fn get_amount_out(amount_in: u64, reserve_a: u64, reserve_b: u64) -> AmountOut {
+    let amount_out = amount_out(
+        amount_in,
+        reserve_a,
+        reserve_b,
+        FEE_NUMERATOR,
+        FEE_DENOMINATOR,
+    );
 
     AmountOut {
         amount_out,
@@ -24,6 +26,8 @@ fn get_amount_out(amount_in: u64, reserve_a: u64, reserve_b: u64) -> AmountOut {
     }
 }
 
+/// Largest front-run in `lb..=ub` after which the user's swap still gets
+/// `user_minimum_amount_out`, or `lb` if even that one leaves them short.
 pub fn calculate_swap_amount_in(
     lb: u64,
     ub: u64,
@@ -32,40 +36,70 @@ pub fn calculate_swap_amount_in(
     reserve_a: u64,
     reserve_b: u64,
 ) -> u64 {
-    let base = 10_000u64;
-    let tolerance = 1u64;
-
-    if ub - lb > (tolerance * ((ub + lb) / 2)) / base {
-        let mid = (lb + ub) / 2;
-
-        let frontrun_state = get_amount_out(mid, reserve_a, reserve_b);
+    let user_filled = |amount_in: u64| {
+        let frontrun_state = get_amount_out(amount_in, reserve_a, reserve_b);
         let victim_state = get_amount_out(
             user_amount_in,
             frontrun_state.new_reserve_a,
             frontrun_state.new_reserve_b,
         );
 
-        let out = victim_state.amount_out;
+        victim_state.amount_out >= user_minimum_amount_out
+    };
+
+    // the guess runs in soft-float on SBF, a fixed sqrt and a dozen float ops
+    // against up to 128 u128 evaluations of `user_filled` without it. It only
+    // picks where the search starts, a wrong one costs steps and never the
+    // result. Its compute units haven't been measured on SBF yet.
+    let guess = estimate_max_front_run(
+        user_amount_in,
+        user_minimum_amount_out,
+        reserve_a,
+        reserve_b,
+        FEE_NUMERATOR,
+        FEE_DENOMINATOR,
+    );
 
-        if out >= user_minimum_amount_out {
-            return calculate_swap_amount_in(
-                mid,
-                ub,
+    largest_filled(lb, ub, guess, user_filled).unwrap_or(lb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn respects_bounds() {
+        // unreachable minimum
+        assert_eq!(calculate_swap_amount_in(5, 100, 10, 1_000, 1_000, 1_000), 5);
+        // no minimum at all
+        assert_eq!(calculate_swap_amount_in(5, 100, 10, 0, 1_000, 1_000), 100);
+    }
+
+    #[test]
+    fn finds_the_boundary() {
+        let (reserve_a, reserve_b) = (48_210_773_019_334, 7_305_118_402_991_006);
+        let user_amount_in = 12_500_000_000;
+        let user_minimum_amount_out = 1_850_000_000_000;
+
+        let victim_out = |amount_in| {
+            let frontrun_state = get_amount_out(amount_in, reserve_a, reserve_b);
+            get_amount_out(
                 user_amount_in,
-                user_minimum_amount_out,
-                reserve_a,
-                reserve_b,
-            );
-        }
-        return calculate_swap_amount_in(
-            lb,
-            mid,
+                frontrun_state.new_reserve_a,
+                frontrun_state.new_reserve_b,
+            )
+            .amount_out
+        };
+
+        let amount_in = calculate_swap_amount_in(
+            0,
+            u64::MAX / 4,
             user_amount_in,
             user_minimum_amount_out,
             reserve_a,
             reserve_b,
         );
+        assert!(victim_out(amount_in) >= user_minimum_amount_out);
+        assert!(victim_out(amount_in + 1) < user_minimum_amount_out);
     }
-
-    (ub + lb) / 2
 }
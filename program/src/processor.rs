@@ -1,4 +1,5 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use sandy_layout::{math::amount_out, RaydiumMarketV4};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -6,7 +7,6 @@ use solana_program::{
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
-    program_pack::Pack,
     pubkey,
     pubkey::Pubkey,
     rent::Rent,
@@ -14,7 +14,7 @@ use solana_program::{
     sysvar::Sysvar,
 };
 use spl_associated_token_account::instruction::create_associated_token_account;
-use spl_token::state::Account;
+use spl_token_2022::{extension::StateWithExtensions, state::Account};
 
 use crate::{
     instruction::{self, SandyInstruction},
@@ -36,6 +36,7 @@ impl Processor {
             SandyInstruction::Initialize(args) => Self::process_initialize(accounts, args),
             SandyInstruction::SwapIn(args) => Self::process_swap_in(accounts, args),
             SandyInstruction::SwapOut => Self::process_swap_out(accounts),
+            SandyInstruction::Guard(args) => Self::process_guard(accounts, args),
         }
     }
 
@@ -122,16 +123,16 @@ impl Processor {
             )?;
         }
 
-        let amm_market = RaydiumMarketV4::try_from_slice(&amm_id.data.borrow()[432..464])?;
+        let quote_mint = RaydiumMarketV4::load(&amm_id.data.borrow())?.quote_mint;
 
-        let mut reserve_a = Account::unpack(&pool_coin_token_account.data.borrow())?.amount;
-        let mut reserve_b = Account::unpack(&pool_pc_token_account.data.borrow())?.amount;
-        if amm_market.quote_mint != *token_address.key {
+        let mut reserve_a = token_amount(pool_coin_token_account)?;
+        let mut reserve_b = token_amount(pool_pc_token_account)?;
+        if quote_mint != *token_address.key {
             // swap reserves
             std::mem::swap(&mut reserve_a, &mut reserve_b);
         }
 
-        let wsol_balance = Account::unpack(&user_source_token_account.data.borrow())?.amount;
+        let wsol_balance = token_amount(user_source_token_account)?;
 
         let mut sandwich_state_data = SandwichState::try_from_slice(&sandwich_state.data.borrow())?;
         sandwich_state_data.preswap_sol_balance = wsol_balance;
@@ -206,7 +207,7 @@ impl Processor {
 
         let sandwich_state_data = SandwichState::try_from_slice(&sandwich_state.data.borrow())?;
 
-        let token_balance = Account::unpack(&user_source_token_account.data.borrow())?.amount;
+        let token_balance = token_amount(user_source_token_account)?;
 
         // swap all tokens out
         let mut data: Vec<u8> = Vec::new();
@@ -242,8 +243,7 @@ impl Processor {
 
         invoke(&instruction, accounts)?;
 
-        let post_swap_balance =
-            Account::unpack(&user_destination_token_account.data.borrow())?.amount;
+        let post_swap_balance = token_amount(user_destination_token_account)?;
 
         // check for underflow, if we are losing money fail the transaction
         let profit = post_swap_balance
@@ -269,6 +269,54 @@ impl Processor {
 
         Ok(())
     }
+
+    fn process_guard(accounts: &[AccountInfo], args: instruction::Guard) -> ProgramResult {
+        msg!("Instruction: Guard");
+
+        let accounts_iter = &mut accounts.iter();
+
+        let amm_id = next_account_info(accounts_iter)?;
+        let pool_in_token_account = next_account_info(accounts_iter)?;
+        let pool_out_token_account = next_account_info(accounts_iter)?;
+
+        if args.tolerance_bps > 10_000 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let amm_data = amm_id.data.borrow();
+        let market = RaydiumMarketV4::load(&amm_data)?;
+        let vaults = (*pool_in_token_account.key, *pool_out_token_account.key);
+        if vaults != (market.base_vault, market.quote_vault)
+            && vaults != (market.quote_vault, market.base_vault)
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // raydium trades against the vaults less the pnl it hasn't taken yet
+        let (need_take_pnl_in, need_take_pnl_out) = if vaults.0 == market.base_vault {
+            (market.base_need_take_pnl, market.quote_need_take_pnl)
+        } else {
+            (market.quote_need_take_pnl, market.base_need_take_pnl)
+        };
+
+        let amount_out = amount_out(
+            args.amount_in,
+            token_amount(pool_in_token_account)?.saturating_sub(need_take_pnl_in),
+            token_amount(pool_out_token_account)?.saturating_sub(need_take_pnl_out),
+            market.swap_fee_numerator,
+            market.swap_fee_denominator,
+        );
+
+        let minimum_amount_out =
+            u128::from(args.quoted_amount_out) * u128::from(10_000 - args.tolerance_bps) / 10_000;
+
+        // the pool moved against the user since the quote
+        if u128::from(amount_out) < minimum_amount_out {
+            return Err(ProgramError::Custom(3));
+        }
+
+        Ok(())
+    }
 }
 
 fn verify_payer(payer: &AccountInfo) -> ProgramResult {
@@ -282,7 +330,9 @@ fn verify_payer(payer: &AccountInfo) -> ProgramResult {
     Ok(())
 }
 
-#[derive(BorshDeserialize)]
-struct RaydiumMarketV4 {
-    pub quote_mint: Pubkey,
+// token accounts of either token program, Token-2022 ones may carry extensions
+// after the base account
+fn token_amount(token_account: &AccountInfo) -> Result<u64, ProgramError> {
+    let data = token_account.data.borrow();
+    Ok(StateWithExtensions::<Account>::unpack(&data)?.base.amount)
 }
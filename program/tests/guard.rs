@@ -0,0 +1,346 @@
+use sandy_analysis::{
+    guard::{GuardBuilder, Quote},
+    pool::Pool,
+};
+use sandy_layout::RaydiumMarketV4;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, hash::Hash, instruction::Instruction,
+    program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+};
+use spl_token_2022::state::{Account, AccountState};
+
+const BASE_RESERVE: u64 = 1_000_000_000_000;
+const QUOTE_RESERVE: u64 = 150_000_000_000;
+const AMOUNT_IN: u64 = 10_000_000_000;
+
+const SMALL_FRONT_RUN: u64 = 500_000_000;
+const LARGE_FRONT_RUN: u64 = 50_000_000_000;
+
+/// A Raydium pool selling base for quote, with just enough state for the
+/// guard to run against.
+struct Harness {
+    amm_id: Pubkey,
+    base_vault: Pubkey,
+    quote_vault: Pubkey,
+    /// What the pool trades against, the vaults also hold the pnl below.
+    pool: Pool,
+    base_need_take_pnl: u64,
+    quote_need_take_pnl: u64,
+}
+
+impl Harness {
+    fn new() -> Self {
+        Self {
+            amm_id: Pubkey::new_unique(),
+            base_vault: Pubkey::new_unique(),
+            quote_vault: Pubkey::new_unique(),
+            pool: Pool::raydium(BASE_RESERVE, QUOTE_RESERVE),
+            base_need_take_pnl: 0,
+            quote_need_take_pnl: 0,
+        }
+    }
+
+    fn front_run(&mut self, amount_in: u64) {
+        self.pool.swap(amount_in);
+    }
+
+    fn quote(&self, amount_out: u64) -> Quote {
+        Quote {
+            amm_id: self.amm_id,
+            pool_in_token_account: self.base_vault,
+            pool_out_token_account: self.quote_vault,
+            amount_in: AMOUNT_IN,
+            amount_out,
+        }
+    }
+
+    fn run(&self, instruction: &Instruction) -> ProgramResult {
+        let market = RaydiumMarketV4 {
+            base_vault: self.base_vault,
+            quote_vault: self.quote_vault,
+            swap_fee_numerator: self.pool.fee_numerator,
+            swap_fee_denominator: self.pool.fee_denominator,
+            base_need_take_pnl: self.base_need_take_pnl,
+            quote_need_take_pnl: self.quote_need_take_pnl,
+            ..bytemuck::Zeroable::zeroed()
+        };
+
+        // account data is 8-aligned on chain, which zero-copy loads rely on
+        let mut market_data = vec![0u64; RaydiumMarketV4::LEN / 8];
+        bytemuck::cast_slice_mut(&mut market_data).copy_from_slice(bytemuck::bytes_of(&market));
+
+        let mut base_data = token_account(self.pool.reserve_in + self.base_need_take_pnl);
+        let mut quote_data = token_account(self.pool.reserve_out + self.quote_need_take_pnl);
+
+        let owner = Pubkey::new_unique();
+        let mut lamports = [0u64; 3];
+        let [market_lamports, base_lamports, quote_lamports] = &mut lamports;
+
+        let accounts = [
+            AccountInfo::new(
+                &self.amm_id,
+                false,
+                false,
+                market_lamports,
+                bytemuck::cast_slice_mut(&mut market_data),
+                &owner,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &self.base_vault,
+                false,
+                false,
+                base_lamports,
+                &mut base_data,
+                &owner,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &self.quote_vault,
+                false,
+                false,
+                quote_lamports,
+                &mut quote_data,
+                &owner,
+                false,
+                0,
+            ),
+        ];
+
+        // in the order the instruction lists them
+        let accounts: Vec<AccountInfo> = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                accounts
+                    .iter()
+                    .find(|account| *account.key == meta.pubkey)
+                    .cloned()
+                    .unwrap_or_else(|| panic!("no account {}", meta.pubkey))
+            })
+            .collect();
+
+        sandy_program::process_instruction(&sandy_program::id(), &accounts, &instruction.data)
+    }
+}
+
+fn token_account(amount: u64) -> Vec<u8> {
+    let mut data = vec![0; Account::LEN];
+    Account {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount,
+        state: AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    data
+}
+
+// the guard passes while quote * (10000 - bps) / 10000, rounded down, is at
+// most amount_out
+fn largest_quote_met(amount_out: u64, tolerance_bps: u16) -> u64 {
+    let largest =
+        ((u128::from(amount_out) + 1) * 10_000).div_ceil(u128::from(10_000 - tolerance_bps)) - 1;
+    largest as u64
+}
+
+enum Quoted {
+    /// What the pool gave before any front-run.
+    Unmoved,
+    /// The largest quote the moved pool still meets within the tolerance.
+    AtTolerance,
+    /// One more than that.
+    PastTolerance,
+}
+
+struct Case {
+    name: &'static str,
+    front_run: u64,
+    tolerance_bps: u16,
+    quoted: Quoted,
+    expected: ProgramResult,
+}
+
+#[test]
+fn guard() {
+    let cases = [
+        Case {
+            name: "unmoved pool, no tolerance",
+            front_run: 0,
+            tolerance_bps: 0,
+            quoted: Quoted::Unmoved,
+            expected: Ok(()),
+        },
+        Case {
+            name: "unmoved pool, 1% tolerance",
+            front_run: 0,
+            tolerance_bps: 100,
+            quoted: Quoted::Unmoved,
+            expected: Ok(()),
+        },
+        Case {
+            name: "small front-run, no tolerance",
+            front_run: SMALL_FRONT_RUN,
+            tolerance_bps: 0,
+            quoted: Quoted::Unmoved,
+            expected: Err(ProgramError::Custom(3)),
+        },
+        Case {
+            name: "small front-run, 1% tolerance",
+            front_run: SMALL_FRONT_RUN,
+            tolerance_bps: 100,
+            quoted: Quoted::Unmoved,
+            expected: Ok(()),
+        },
+        Case {
+            name: "large front-run, 1% tolerance",
+            front_run: LARGE_FRONT_RUN,
+            tolerance_bps: 100,
+            quoted: Quoted::Unmoved,
+            expected: Err(ProgramError::Custom(3)),
+        },
+        Case {
+            name: "large front-run, full tolerance",
+            front_run: LARGE_FRONT_RUN,
+            tolerance_bps: 10_000,
+            quoted: Quoted::Unmoved,
+            expected: Ok(()),
+        },
+        Case {
+            name: "moved pool, quote at the tolerance",
+            front_run: LARGE_FRONT_RUN,
+            tolerance_bps: 100,
+            quoted: Quoted::AtTolerance,
+            expected: Ok(()),
+        },
+        Case {
+            name: "moved pool, quote just past the tolerance",
+            front_run: LARGE_FRONT_RUN,
+            tolerance_bps: 100,
+            quoted: Quoted::PastTolerance,
+            expected: Err(ProgramError::Custom(3)),
+        },
+        Case {
+            name: "tolerance over 100%",
+            front_run: 0,
+            tolerance_bps: 10_001,
+            quoted: Quoted::Unmoved,
+            expected: Err(ProgramError::InvalidInstructionData),
+        },
+    ];
+
+    for case in cases {
+        let mut harness = Harness::new();
+        let unmoved_amount_out = harness.pool.amount_out(AMOUNT_IN);
+
+        harness.front_run(case.front_run);
+        let amount_out = harness.pool.amount_out(AMOUNT_IN);
+
+        let quoted_amount_out = match case.quoted {
+            Quoted::Unmoved => unmoved_amount_out,
+            Quoted::AtTolerance => largest_quote_met(amount_out, case.tolerance_bps),
+            Quoted::PastTolerance => largest_quote_met(amount_out, case.tolerance_bps) + 1,
+        };
+
+        let builder = GuardBuilder::new(
+            sandy_program::id(),
+            harness.quote(quoted_amount_out),
+            case.tolerance_bps,
+        );
+
+        assert_eq!(
+            harness.run(&builder.guard_instruction()),
+            case.expected,
+            "{}",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn guard_prices_net_of_unclaimed_pnl() {
+    let mut harness = Harness::new();
+    harness.base_need_take_pnl = 20_000_000_000;
+    harness.quote_need_take_pnl = 3_000_000_000;
+
+    let amount_out = harness.pool.amount_out(AMOUNT_IN);
+    // what the raw vault balances would promise
+    let vault_amount_out = Pool::raydium(
+        BASE_RESERVE + harness.base_need_take_pnl,
+        QUOTE_RESERVE + harness.quote_need_take_pnl,
+    )
+    .amount_out(AMOUNT_IN);
+    assert!(vault_amount_out > amount_out + 1);
+
+    for (quoted_amount_out, expected) in [
+        (amount_out, Ok(())),
+        (amount_out + 1, Err(ProgramError::Custom(3))),
+        (vault_amount_out, Err(ProgramError::Custom(3))),
+    ] {
+        let builder = GuardBuilder::new(sandy_program::id(), harness.quote(quoted_amount_out), 0);
+
+        assert_eq!(
+            harness.run(&builder.guard_instruction()),
+            expected,
+            "quoted {quoted_amount_out}"
+        );
+    }
+}
+
+#[test]
+fn guard_checks_the_vaults_belong_to_the_pool() {
+    let harness = Harness::new();
+    let quote = harness.quote(harness.pool.amount_out(AMOUNT_IN));
+    let builder = GuardBuilder::new(sandy_program::id(), quote, 0);
+
+    // any account that isn't one of the pool's vaults
+    let mut instruction = builder.guard_instruction();
+    instruction.accounts[2].pubkey = harness.amm_id;
+
+    assert_eq!(
+        harness.run(&instruction),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+fn guard_checks_either_direction() {
+    let harness = Harness::new();
+    let amount_out = harness.pool.reversed().amount_out(AMOUNT_IN);
+
+    let quote = Quote {
+        pool_in_token_account: harness.quote_vault,
+        pool_out_token_account: harness.base_vault,
+        ..harness.quote(amount_out)
+    };
+    let builder = GuardBuilder::new(sandy_program::id(), quote, 0);
+
+    assert_eq!(harness.run(&builder.guard_instruction()), Ok(()));
+}
+
+#[test]
+fn guard_goes_in_front_of_the_swap() {
+    let harness = Harness::new();
+    let builder = GuardBuilder::new(sandy_program::id(), harness.quote(1), 50);
+
+    let swap = Instruction::new_with_bytes(Pubkey::new_unique(), &[9], vec![]);
+    let payer = Pubkey::new_unique();
+    let transaction = builder.transaction(std::slice::from_ref(&swap), &payer, Hash::default());
+
+    let message = &transaction.message;
+    assert_eq!(message.account_keys[0], payer);
+    assert_eq!(message.instructions.len(), 2);
+    assert_eq!(
+        message.program_id(0),
+        Some(&sandy_program::id()),
+        "guard first"
+    );
+    assert_eq!(message.program_id(1), Some(&swap.program_id));
+    assert_eq!(
+        message.instructions[0].data,
+        builder.guard_instruction().data
+    );
+}
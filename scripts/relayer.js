@@ -18,6 +18,7 @@ const ws = require('ws');
         data: Array.from(serializedTransaction),
         meta: {
           size: serializedTransaction.length,
+          timestamp_us: Date.now() * 1000,
         }
       }],
     }